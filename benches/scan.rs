@@ -0,0 +1,84 @@
+//! Throughput of delimiter scanning, run with `cargo bench`
+
+use std::hint::black_box;
+use std::num::NonZeroUsize;
+use std::thread;
+use std::time::{Duration, Instant};
+use blocks::{Blocks, ByteDelimiters, Event, Lanes};
+
+const SIZE: usize = 64 * 1024 * 1024;
+const ROUNDS: usize = 5;
+
+/// JSON-like input: nested objects and arrays separated by long runs of text
+fn input() -> Vec<u8> {
+    let record = br#"{"id": 12345, "name": "a fairly long string value", "tags": ["x", "y"], "nested": {"list": [1, 2, 3], "text": "lorem ipsum dolor sit amet"}}, "#;
+    let mut bytes = b"[".to_vec();
+    
+    while bytes.len() + record.len() + 1 < SIZE {
+        bytes.extend_from_slice(record);
+    }
+    
+    bytes.truncate(bytes.len() - 2);
+    bytes.push(b']');
+    bytes
+}
+
+fn bench(name: &str, bytes: &[u8], mut f: impl FnMut(&[u8]) -> usize) {
+    let mut best = Duration::MAX;
+    
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        black_box(f(black_box(bytes)));
+        best = best.min(start.elapsed());
+    }
+    
+    let throughput = bytes.len() as f64 / best.as_secs_f64() / 1e9;
+    println!("{name:<24} {:>8.2} ms {throughput:>8.2} GB/s", best.as_secs_f64() * 1e3);
+}
+
+fn main() {
+    let bytes = input();
+    let delimiters = ByteDelimiters::brackets();
+    
+    bench("scalar add_left/right", &bytes, |bytes| {
+        let mut blocks = Blocks::new();
+        
+        for (n, &b) in bytes.iter().enumerate() {
+            match b {
+                b'(' => blocks.add_left_kind(n, 0).unwrap(),
+                b'[' => blocks.add_left_kind(n, 1).unwrap(),
+                b'{' => blocks.add_left_kind(n, 2).unwrap(),
+                b')' => blocks.add_right_kind(n, 0).unwrap(),
+                b']' => blocks.add_right_kind(n, 1).unwrap(),
+                b'}' => blocks.add_right_kind(n, 2).unwrap(),
+                _ => {}
+            }
+        }
+        
+        blocks.consume().unwrap().len()
+    });
+    
+    for lanes in [Lanes::Scalar, Lanes::Sse2, Lanes::Avx2] {
+        bench(&format!("find {lanes:?}"), &bytes, |bytes| {
+            let mut count = 0;
+            delimiters.find_with(bytes, lanes, |_, _| count += 1);
+            count
+        });
+    }
+    
+    for lanes in [Lanes::Scalar, Lanes::Sse2, Lanes::Avx2] {
+        bench(&format!("balance {lanes:?}"), &bytes, |bytes| {
+            let mut blocks = Blocks::new();
+            
+            delimiters.find_with(bytes, lanes, |n, event: Event<usize>| blocks.add(n, event).unwrap());
+            blocks.consume().unwrap().len()
+        });
+    }
+    
+    bench("Blocks::scan_bytes", &bytes, |bytes| Blocks::scan_bytes(bytes, &delimiters).unwrap().len());
+    
+    let threads = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    bench(&format!("scan_bytes_parallel x{threads}"), &bytes, |bytes| {
+        Blocks::scan_bytes_parallel(bytes, &delimiters, threads).unwrap().len()
+    });
+}
@@ -0,0 +1,215 @@
+use core::fmt::{self, Debug, Formatter};
+use core::mem::MaybeUninit;
+use core::num::NonZeroUsize;
+use core::ptr;
+use crate::{BalanceBlockError, Balanced, Block};
+
+/// Marks the absence of an enclosing open block
+const NONE: usize = usize::MAX;
+
+/// Closing index of an open block, past any token
+const OPEN: usize = usize::MAX;
+
+/** A left-to-right block processor with a fixed capacity of `N` blocks.
+    Never allocates; going past the capacity yields `BalanceBlockError::CapacityExceeded`.
+    Open blocks are chained through the length of their closing token, so nesting needs no extra storage. */
+pub struct ArrayBlocks<const N: usize, K = ()> {
+    inner: [MaybeUninit<Block<Balanced, K>>; N],
+    len: usize,
+    top: usize
+}
+
+impl<const N: usize> ArrayBlocks<N> {
+    /// Add a new opening token to the list
+    #[inline(always)]
+    pub fn add_left(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
+        self.add_left_kind(idx, ())
+    }
+    
+    /// Add a new closing token to the list, closing the innermost open block
+    #[inline(always)]
+    pub fn add_right(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
+        self.add_right_kind(idx, ())
+    }
+}
+
+impl<const N: usize, K> ArrayBlocks<N, K> {
+    /// Construct an empty `ArrayBlocks` structure
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            inner: [const {MaybeUninit::uninit()}; N],
+            len: 0,
+            top: NONE
+        }
+    }
+    
+    /// Retrieve the maximum number of blocks
+    #[inline(always)]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+    
+    /// Retrieve the number of blocks, open or closed
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    
+    /// Check whether no block was opened
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Add a new opening token of the given kind to the list
+    pub fn add_left_kind(&mut self, idx: usize, kind: K) -> Result<(), BalanceBlockError<K>> {
+        let slot = self.inner
+            .get_mut(self.len)
+            .ok_or(BalanceBlockError::CapacityExceeded {capacity: N})?;
+        
+        // The closing length of an open block links to the enclosing open block
+        slot.write(Block {
+            opening: idx,
+            closing: OPEN,
+            kind,
+            opening_len: 1,
+            closing_len: self.top
+        });
+        self.top = self.len;
+        self.len += 1;
+        Ok(())
+    }
+    
+    /// Check whether the tokens are balanced
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        self.top == NONE
+    }
+    
+    /// Iterate over the opening indices of the blocks that are still open, from the outermost to the innermost
+    pub fn unclosed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.as_slice()
+            .iter()
+            .filter(|block| block.closing == OPEN)
+            .map(|block| block.opening)
+    }
+    
+    /** Check the validity of the structure and return a slice of balanced blocks.
+        The blocks are ordered by their opening index */
+    pub fn blocks(&self) -> Result<&[Block<Balanced, K>], BalanceBlockError<K>> {
+        if !self.is_valid() {
+            return Err(BalanceBlockError::ExtraLeft {
+                #[cfg(feature = "alloc")]
+                unclosed: self.unclosed().collect()
+            });
+        }
+        
+        Ok(self.as_slice())
+    }
+    
+    /// Retrieve every block, open ones included
+    #[inline(always)]
+    fn as_slice(&self) -> &[Block<Balanced, K>] {
+        // SAFETY: the first `len` blocks are initialized
+        unsafe {&*(ptr::from_ref(&self.inner[..self.len]) as *const [Block<Balanced, K>])}
+    }
+}
+
+impl<const N: usize, K: PartialEq + Clone> ArrayBlocks<N, K> {
+    /** Add a new closing token of the given kind to the list,
+        closing the innermost open block.
+        The token must follow the opening token */
+    pub fn add_right_kind(&mut self, idx: usize, kind: K) -> Result<(), BalanceBlockError<K>> {
+        if self.top == NONE {
+            return Err(BalanceBlockError::ExtraRight {closing: idx});
+        }
+        
+        // SAFETY: `top` is always an initialized block
+        let block = unsafe {self.inner[self.top].assume_init_mut()};
+        
+        if idx <= block.opening || idx == OPEN {
+            return Err(BalanceBlockError::InvalidToken {start: idx, end: idx.saturating_add(1)});
+        }
+        
+        if block.kind != kind {
+            return Err(BalanceBlockError::Mismatch {
+                expected: block.kind.clone(),
+                found: kind,
+                opening: block.opening,
+                closing: idx
+            });
+        }
+        
+        self.top = core::mem::replace(&mut block.closing_len, 1);
+        block.closing = idx;
+        Ok(())
+    }
+}
+
+impl<const N: usize, K> Default for ArrayBlocks<N, K> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, K: Clone> Clone for ArrayBlocks<N, K> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        
+        for (slot, block) in clone.inner.iter_mut().zip(self.as_slice()) {
+            slot.write(block.clone());
+        }
+        
+        clone.len = self.len;
+        clone.top = self.top;
+        clone
+    }
+}
+
+impl<const N: usize, K: Debug> Debug for ArrayBlocks<N, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArrayBlocks")
+            .field("inner", &Entries(self.as_slice()))
+            .finish()
+    }
+}
+
+/// Blocks shown like in `Blocks`, open ones as unbalanced blocks rather than with their links
+struct Entries<'a, K>(&'a [Block<Balanced, K>]);
+
+impl<K: Debug> Debug for Entries<'_, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter().map(Entry)).finish()
+    }
+}
+
+/// A block of `Entries`
+struct Entry<'a, K>(&'a Block<Balanced, K>);
+
+impl<K: Debug> Debug for Entry<'_, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Entry(block) = self;
+        
+        match block.closing {
+            OPEN => f.debug_struct("Block")
+                .field("opening", &block.opening)
+                .field("closing", &None::<NonZeroUsize>)
+                .field("kind", &block.kind)
+                .field("opening_len", &block.opening_len)
+                .field("closing_len", &0)
+                .finish(),
+            _ => block.fmt(f)
+        }
+    }
+}
+
+impl<const N: usize, K> Drop for ArrayBlocks<N, K> {
+    fn drop(&mut self) {
+        for slot in &mut self.inner[..self.len] {
+            // SAFETY: the first `len` blocks are initialized and dropped once
+            unsafe {slot.assume_init_drop()}
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use core::convert::Infallible;
+use crate::Event;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use crate::{BalanceBlockError, Balanced, Block, Blocks};
+
+/// Maximum number of distinct delimiter bytes searched with vector instructions
+const MAX_NEEDLES: usize = 16;
+
+/// Lane width used to search for delimiter bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Lanes {
+    /// The widest lanes the running CPU supports on x86-64, `Scalar` on other targets
+    #[default]
+    Auto,
+    /// One byte at a time through a lookup table, on any target
+    Scalar,
+    /// 16-byte SSE2 lanes, on x86-64
+    Sse2,
+    /// 32-byte AVX2 lanes, on x86-64 CPUs supporting it
+    Avx2
+}
+
+/** A set of single-byte delimiter pairs searched with vector instructions.
+    The kind of a block is the position of its pair in the set.
+    Sets of more than 8 pairs are searched one byte at a time. */
+#[derive(Clone, Debug)]
+pub struct ByteDelimiters {
+    // 0 for other bytes, `2 * kind + 1` for openings and `2 * kind + 2` for closings
+    table: [u8; 256],
+    needles: [u8; MAX_NEEDLES],
+    len: usize
+}
+
+impl ByteDelimiters {
+    /// Construct an empty set
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            table: [0; 256],
+            needles: [0; MAX_NEEDLES],
+            len: 0
+        }
+    }
+    
+    /// Construct the set of `()`, `[]` and `{}`, in this order
+    pub const fn brackets() -> Self {
+        Self::new()
+            .pair(b'(', b')')
+            .pair(b'[', b']')
+            .pair(b'{', b'}')
+    }
+    
+    /** Add a pair of an opening and a closing byte.
+        Panics if a byte is already in the set or if there are more than 127 pairs */
+    pub const fn pair(mut self, open: u8, close: u8) -> Self {
+        assert!(self.len < 127, "too many delimiter pairs");
+        assert!(open != close && self.table[open as usize] == 0 && self.table[close as usize] == 0, "duplicate delimiter");
+        
+        self.table[open as usize] = 2 * self.len as u8 + 1;
+        self.table[close as usize] = 2 * self.len as u8 + 2;
+        
+        if self.len < MAX_NEEDLES / 2 {
+            self.needles[2 * self.len] = open;
+            self.needles[2 * self.len + 1] = close;
+        }
+        
+        self.len += 1;
+        self
+    }
+    
+    /// Retrieve the number of pairs
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    
+    /// Check whether the set has no pairs
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Classify a byte as a token
+    #[inline(always)]
+    pub const fn classify(&self, byte: u8) -> Option<Event<usize>> {
+        match self.table[byte as usize] {
+            0 => None,
+            code if code % 2 == 1 => Some(Event::Open(code as usize / 2)),
+            code => Some(Event::Close(code as usize / 2 - 1))
+        }
+    }
+    
+    /// Call `f` with the index and the token of every delimiter byte, in order
+    #[inline(always)]
+    pub fn find(&self, bytes: &[u8], f: impl FnMut(usize, Event<usize>)) {
+        self.find_with(bytes, Lanes::Auto, f)
+    }
+    
+    /** Call `f` with the index and the token of every delimiter byte, in order,
+        searching with the given lanes. Lanes unavailable on the running CPU fall back to `Lanes::Auto` */
+    #[inline(always)]
+    pub fn find_with(&self, bytes: &[u8], lanes: Lanes, mut f: impl FnMut(usize, Event<usize>)) {
+        let Ok(()) = self.try_find_with(bytes, lanes, |idx, event| {
+            f(idx, event);
+            Ok::<_, Infallible>(())
+        });
+    }
+    
+    /// Call `f` with the index and the token of every delimiter byte, in order, until it fails
+    #[inline(always)]
+    pub fn try_find<E>(&self, bytes: &[u8], f: impl FnMut(usize, Event<usize>) -> Result<(), E>) -> Result<(), E> {
+        self.try_find_with(bytes, Lanes::Auto, f)
+    }
+    
+    /** Call `f` with the index and the token of every delimiter byte, in order, until it fails,
+        searching with the given lanes. Lanes unavailable on the running CPU fall back to `Lanes::Auto` */
+    pub fn try_find_with<E>(
+        &self,
+        bytes: &[u8],
+        lanes: Lanes,
+        mut f: impl FnMut(usize, Event<usize>) -> Result<(), E>
+    ) -> Result<(), E> {
+        let mut hit = |idx: usize| match self.classify(bytes[idx]) {
+            Some(event) => f(idx, event),
+            None => Ok(())
+        };
+        
+        let done = match self.lanes(lanes) {
+            Lanes::Scalar => 0,
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: SSE2 is part of the x86-64 baseline
+            Lanes::Sse2 => unsafe {x86::find_sse2(self.needles(), bytes, &mut hit)?},
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: AVX2 support was checked by `Self::lanes`
+            Lanes::Avx2 => unsafe {x86::find_avx2(self.needles(), bytes, &mut hit)?},
+            _ => unreachable!()
+        };
+        
+        // The tail shorter than a lane goes one byte at a time
+        (done..bytes.len()).try_for_each(hit)
+    }
+    
+    /// Resolve the lanes to use on the running CPU
+    fn lanes(&self, lanes: Lanes) -> Lanes {
+        if self.len > MAX_NEEDLES / 2 || lanes == Lanes::Scalar {
+            return Lanes::Scalar;
+        }
+        
+        #[cfg(target_arch = "x86_64")]
+        {
+            #[cfg(feature = "std")]
+            let avx2 = std::is_x86_feature_detected!("avx2");
+            #[cfg(not(feature = "std"))]
+            let avx2 = cfg!(target_feature = "avx2");
+            
+            match lanes {
+                Lanes::Sse2 => lanes,
+                _ if avx2 => Lanes::Avx2,
+                _ => Lanes::Sse2
+            }
+        }
+        
+        #[cfg(not(target_arch = "x86_64"))]
+        Lanes::Scalar
+    }
+    
+    #[cfg(target_arch = "x86_64")]
+    #[inline(always)]
+    fn needles(&self) -> &[u8] {
+        &self.needles[..2 * self.len]
+    }
+}
+
+impl Default for ByteDelimiters {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Blocks<usize> {
+    /** Balance the delimiter bytes of a buffer, skipping every other byte.
+        Kinds are positions in `delimiters` */
+    pub fn scan_bytes(bytes: &[u8], delimiters: &ByteDelimiters) -> Result<Vec<Block<Balanced, usize>>, BalanceBlockError<usize>> {
+        let mut blocks = Self::new();
+        
+        delimiters.try_find(bytes, |idx, event| blocks.add(idx, event))?;
+        blocks.consume()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+    use super::MAX_NEEDLES;
+    
+    /// Report candidates in 16-byte lanes until `hit` fails, returning the length processed
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn find_sse2<E>(needles: &[u8], bytes: &[u8], hit: &mut impl FnMut(usize) -> Result<(), E>) -> Result<usize, E> {
+        let mut splat = [_mm_setzero_si128(); MAX_NEEDLES];
+        
+        for (splat, &needle) in splat.iter_mut().zip(needles) {
+            *splat = _mm_set1_epi8(needle as i8);
+        }
+        
+        let splat = &splat[..needles.len()];
+        let mut base = 0;
+        
+        for lane in bytes.chunks_exact(16) {
+            // SAFETY: the lane is 16 bytes long
+            let lane = unsafe {_mm_loadu_si128(lane.as_ptr().cast())};
+            let mut mask = splat
+                .iter()
+                .fold(0, |mask, &splat| mask | _mm_movemask_epi8(_mm_cmpeq_epi8(lane, splat)) as u32);
+            
+            while mask != 0 {
+                hit(base + mask.trailing_zeros() as usize)?;
+                mask &= mask - 1;
+            }
+            
+            base += 16;
+        }
+        
+        Ok(base)
+    }
+    
+    /// Report candidates in 32-byte lanes until `hit` fails, returning the length processed
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn find_avx2<E>(needles: &[u8], bytes: &[u8], hit: &mut impl FnMut(usize) -> Result<(), E>) -> Result<usize, E> {
+        let mut splat = [_mm256_setzero_si256(); MAX_NEEDLES];
+        
+        for (splat, &needle) in splat.iter_mut().zip(needles) {
+            *splat = _mm256_set1_epi8(needle as i8);
+        }
+        
+        let splat = &splat[..needles.len()];
+        let mut base = 0;
+        
+        for lane in bytes.chunks_exact(32) {
+            // SAFETY: the lane is 32 bytes long
+            let lane = unsafe {_mm256_loadu_si256(lane.as_ptr().cast())};
+            let mut mask = splat
+                .iter()
+                .fold(0, |mask, &splat| mask | _mm256_movemask_epi8(_mm256_cmpeq_epi8(lane, splat)) as u32);
+            
+            while mask != 0 {
+                hit(base + mask.trailing_zeros() as usize)?;
+                mask &= mask - 1;
+            }
+            
+            base += 32;
+        }
+        
+        Ok(base)
+    }
+}
@@ -0,0 +1,228 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+use crate::{BalanceBlockError, Balanced, Block, Blocks, Event};
+
+/// Blocks affected by an edit
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct Changes<K = ()> {
+    /// Blocks that did not exist before the edit, at their new position
+    pub created: Vec<Block<Balanced, K>>,
+    /// Blocks that no longer exist after the edit, at their old position
+    pub destroyed: Vec<Block<Balanced, K>>,
+    /// Blocks that survived the edit but moved, at their new position
+    pub shifted: Vec<Block<Balanced, K>>
+}
+
+/** Balanced blocks of a document kept up to date under edits.
+    Characters are classified into tokens by `F`; indices are byte offsets.
+    An edit only re-matches the tokens of the innermost block enclosing it
+    that stays balanced, falling back to the whole document otherwise. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct Incremental<K, F> {
+    classify: F,
+    len: usize,
+    tokens: Vec<(usize, Event<K>)>,
+    blocks: Vec<Block<Balanced, K>>,
+    error: Option<BalanceBlockError<K>>
+}
+
+impl<K, F> Incremental<K, F>
+where
+    K: PartialEq + Clone,
+    F: FnMut(char) -> Option<Event<K>>
+{
+    /// Scan a document with a character classifier
+    pub fn new(text: &str, mut classify: F) -> Self {
+        let tokens: Vec<_> = text
+            .char_indices()
+            .filter_map(|(n, c)| Some((n, classify(c)?)))
+            .collect();
+        let (blocks, error) = match rematch(&tokens) {
+            Ok(blocks) => (blocks, None),
+            Err(err) => (Vec::new(), Some(err))
+        };
+        
+        Self {
+            classify,
+            len: text.len(),
+            tokens,
+            blocks,
+            error
+        }
+    }
+    
+    /// Retrieve the length of the document in bytes
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    /// Check whether the document is empty
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Retrieve the balanced blocks of the document, ordered by their opening index
+    pub fn blocks(&self) -> Result<&[Block<Balanced, K>], &BalanceBlockError<K>> {
+        match &self.error {
+            None => Ok(&self.blocks),
+            Some(err) => Err(err)
+        }
+    }
+    
+    /** Insert text at a byte offset.
+        Panics if `at` is past the end of the document */
+    #[inline(always)]
+    pub fn insert(&mut self, at: usize, text: &str) -> Result<Changes<K>, BalanceBlockError<K>> {
+        self.replace(at..at, text)
+    }
+    
+    /** Delete a byte range.
+        Panics if the range is past the end of the document */
+    #[inline(always)]
+    pub fn delete(&mut self, range: Range<usize>) -> Result<Changes<K>, BalanceBlockError<K>> {
+        self.replace(range, "")
+    }
+    
+    /** Replace a byte range with text.
+        Panics if the range is past the end of the document */
+    pub fn replace(&mut self, range: Range<usize>, text: &str) -> Result<Changes<K>, BalanceBlockError<K>> {
+        let Range {start, end} = range;
+        assert!(start <= end && end <= self.len, "edit {start}..{end} out of bounds of {}", self.len);
+        
+        let shift = Shift {start, end, added: text.len()};
+        let classify = &mut self.classify;
+        let inserted = text
+            .char_indices()
+            .filter_map(|(n, c)| Some((start + n, classify(c)?)));
+        
+        let lo = self.tokens.partition_point(|&(idx, _)| idx < start);
+        let hi = self.tokens.partition_point(|&(idx, _)| idx < end);
+        let before = self.tokens.len();
+        self.tokens.splice(lo..hi, inserted);
+        let tail = hi + self.tokens.len() - before;
+        
+        for (idx, _) in &mut self.tokens[tail..] {
+            *idx = shift.apply(*idx).expect("token after the edit");
+        }
+        
+        self.len = self.len - (end - start) + text.len();
+        
+        if self.error.is_none() {
+            // Enclosing blocks are ordered from the outermost to the innermost
+            let enclosing: Vec<_> = (0..self.blocks.len())
+                .filter(|&i| self.blocks[i].opening < start && end <= self.blocks[i].closing)
+                .collect();
+            
+            for &i in enclosing.iter().rev() {
+                let block = &self.blocks[i];
+                let closing = shift.apply(block.closing).expect("closing token after the edit");
+                let lo = self.tokens.partition_point(|&(idx, _)| idx <= block.opening);
+                let hi = self.tokens.partition_point(|&(idx, _)| idx < closing);
+                let descendants = self.blocks[i + 1..].partition_point(|other| other.opening < block.closing);
+                
+                if let Ok(rematched) = rematch(&self.tokens[lo..hi]) {
+                    return Ok(self.splice(i + 1..i + 1 + descendants, rematched, shift));
+                }
+            }
+        }
+        
+        match rematch(&self.tokens) {
+            Ok(rematched) => {
+                self.error = None;
+                Ok(self.splice(0..self.blocks.len(), rematched, shift))
+            }
+            Err(err) => {
+                self.blocks.clear();
+                self.error = Some(err.clone());
+                Err(err)
+            }
+        }
+    }
+    
+    /// Replace the blocks in `old` with `rematched`, shifting the others
+    fn splice(&mut self, old: Range<usize>, rematched: Vec<Block<Balanced, K>>, shift: Shift) -> Changes<K> {
+        let mut changes = Changes {
+            created: Vec::new(),
+            destroyed: Vec::new(),
+            shifted: Vec::new()
+        };
+        
+        let (before, rest) = self.blocks.split_at_mut(old.start);
+        let (region, after) = rest.split_at_mut(old.len());
+        
+        // Blocks outside of the region keep both of their delimiters
+        for block in before.iter_mut().chain(after) {
+            let (opening, closing) = shift.block(block).expect("block outside of the edit");
+            
+            if (opening, closing) != (block.opening, block.closing) {
+                (block.opening, block.closing) = (opening, closing);
+                changes.shifted.push(block.clone());
+            }
+        }
+        
+        // Both sides are ordered by their opening index
+        let mut new = rematched.iter().peekable();
+        
+        for block in region.iter() {
+            let Some((opening, closing)) = shift.block(block) else {
+                changes.destroyed.push(block.clone());
+                continue;
+            };
+            
+            while let Some(created) = new.next_if(|new| new.opening < opening) {
+                changes.created.push(created.clone());
+            }
+            
+            match new.next_if(|new| (new.opening, new.closing) == (opening, closing)) {
+                Some(same) if (opening, closing) != (block.opening, block.closing) => {
+                    changes.shifted.push(same.clone())
+                }
+                Some(_) => {}
+                None => changes.destroyed.push(block.clone())
+            }
+        }
+        
+        changes.created.extend(new.cloned());
+        self.blocks.splice(old, rematched);
+        changes
+    }
+}
+
+/// Mapping of indices across an edit replacing `start..end` with `added` bytes
+#[derive(Clone, Copy, Debug)]
+struct Shift {
+    start: usize,
+    end: usize,
+    added: usize
+}
+
+impl Shift {
+    /// Map an index, `None` if it was removed by the edit
+    fn apply(self, idx: usize) -> Option<usize> {
+        match idx {
+            _ if idx < self.start => Some(idx),
+            _ if idx >= self.end => Some(idx - (self.end - self.start) + self.added),
+            _ => None
+        }
+    }
+    
+    /// Map both delimiters of a block
+    fn block<K>(self, block: &Block<Balanced, K>) -> Option<(usize, usize)> {
+        Some((self.apply(block.opening)?, self.apply(block.closing)?))
+    }
+}
+
+/// Match a run of tokens on their own
+fn rematch<K: PartialEq + Clone>(tokens: &[(usize, Event<K>)]) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>> {
+    let mut blocks = Blocks::new();
+    
+    for (idx, event) in tokens {
+        blocks.add(*idx, event.clone())?;
+    }
+    
+    blocks.consume()
+}
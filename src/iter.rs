@@ -0,0 +1,69 @@
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ops::Range;
+use crate::{BalanceBlockError, Balanced, Block, Blocks, Event};
+
+/** Balancing of indexed token streams, such as `str::char_indices`.
+    Implemented for every iterator over `(index, token)` pairs. */
+pub trait BalanceExt<T>: Iterator<Item = (usize, T)> + Sized {
+    /** Classify every token and balance the delimiters, skipping the tokens classified as `None`.
+        Stops at the first error */
+    fn balance<K, F>(self, mut classify: F) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>>
+    where
+        K: PartialEq + Clone,
+        F: FnMut(T) -> Option<Event<K>>
+    {
+        let mut blocks = Blocks::new();
+        
+        for (idx, token) in self {
+            if let Some(event) = classify(token) {
+                blocks.add(idx, event)?;
+            }
+        }
+        
+        blocks.consume()
+    }
+}
+
+impl<T, I: Iterator<Item = (usize, T)>> BalanceExt<T> for I {}
+
+/** Tokens are added in order.
+    The first error is kept and returned by `Blocks::consume`, the following tokens being ignored */
+impl<K: PartialEq + Clone> Extend<(usize, Event<K>)> for Blocks<K> {
+    #[inline(always)]
+    fn extend<I: IntoIterator<Item = (usize, Event<K>)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(idx, event)| (idx..idx + 1, event)))
+    }
+}
+
+/** Tokens spanning ranges, such as the ones of `Delimiters::tokens`, are added in order.
+    The first error is kept and returned by `Blocks::consume`, the following tokens being ignored */
+impl<K: PartialEq + Clone> Extend<(Range<usize>, Event<K>)> for Blocks<K> {
+    fn extend<I: IntoIterator<Item = (Range<usize>, Event<K>)>>(&mut self, iter: I) {
+        for (range, event) in iter {
+            if self.error.is_some() {
+                return;
+            }
+            
+            self.error = self.add_token(range, event).err();
+        }
+    }
+}
+
+impl<K: PartialEq + Clone> FromIterator<(usize, Event<K>)> for Blocks<K> {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = (usize, Event<K>)>>(iter: I) -> Self {
+        let mut blocks = Self::new();
+        blocks.extend(iter);
+        blocks
+    }
+}
+
+impl<K: PartialEq + Clone> FromIterator<(Range<usize>, Event<K>)> for Blocks<K> {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = (Range<usize>, Event<K>)>>(iter: I) -> Self {
+        let mut blocks = Self::new();
+        blocks.extend(iter);
+        blocks
+    }
+}
@@ -0,0 +1,78 @@
+use alloc::{vec, vec::Vec};
+use crate::{BalanceBlockError, Balanced, Block, Blocks};
+
+/** A dense table mapping every delimiter index to the index of its partner.
+    Lookups are a single array access, fitting the hot loops of bytecode interpreters. */
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JumpTable {
+    inner: Vec<usize>
+}
+
+impl JumpTable {
+    /// Marks a position that holds no delimiter
+    const NONE: usize = usize::MAX;
+    
+    /// Build a table from balanced blocks
+    pub fn new<K>(blocks: &[Block<Balanced, K>]) -> Self {
+        let len = blocks
+            .iter()
+            .map(|block| block.closing + 1)
+            .max()
+            .unwrap_or(0);
+        let mut inner = vec![Self::NONE; len];
+        
+        for block in blocks {
+            inner[block.opening] = block.closing;
+            inner[block.closing] = block.opening;
+        }
+        
+        Self {inner}
+    }
+    
+    /// Retrieve the index of the partner of the delimiter at `idx`
+    #[inline(always)]
+    pub fn partner(&self, idx: usize) -> Option<usize> {
+        self.inner
+            .get(idx)
+            .copied()
+            .filter(|&partner| partner != Self::NONE)
+    }
+    
+    /// Retrieve the index of the partner of the delimiter at `idx` without any checks
+    ///
+    /// # Safety
+    /// `idx` must be less than `self.len()` and hold a delimiter
+    #[inline(always)]
+    pub unsafe fn partner_unchecked(&self, idx: usize) -> usize {
+        // SAFETY: upheld by the caller
+        unsafe {*self.inner.get_unchecked(idx)}
+    }
+    
+    /// Retrieve the number of positions covered by the table
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    
+    /// Check whether the table covers no positions
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<K> Blocks<K> {
+    /// Check the validity of the structure and return a jump table of the blocks
+    #[inline(always)]
+    pub fn into_jump_table(self) -> Result<JumpTable, BalanceBlockError<K>> {
+        self.consume().map(|blocks| JumpTable::new(&blocks))
+    }
+}
+
+impl<K> From<&[Block<Balanced, K>]> for JumpTable {
+    #[inline(always)]
+    fn from(blocks: &[Block<Balanced, K>]) -> Self {
+        Self::new(blocks)
+    }
+}
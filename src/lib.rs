@@ -1,5 +1,79 @@
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
 use core::num::NonZeroUsize;
-use thiserror::Error;
+use core::ops::{Index, Range};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+mod array;
+mod bytes;
+#[cfg(feature = "alloc")]
+mod incremental;
+#[cfg(feature = "alloc")]
+mod iter;
+#[cfg(feature = "alloc")]
+mod jump;
+#[cfg(feature = "alloc")]
+mod limits;
+#[cfg(feature = "alloc")]
+mod lines;
+#[cfg(feature = "std")]
+mod parallel;
+#[cfg(feature = "alloc")]
+mod recover;
+#[cfg(feature = "alloc")]
+mod report;
+#[cfg(feature = "alloc")]
+mod rev;
+#[cfg(feature = "alloc")]
+mod scan;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "alloc")]
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod tree;
+#[cfg(feature = "alloc")]
+mod visit;
+
+pub use array::ArrayBlocks;
+pub use bytes::{ByteDelimiters, Lanes};
+#[cfg(feature = "alloc")]
+pub use incremental::{Changes, Incremental};
+#[cfg(feature = "alloc")]
+pub use iter::BalanceExt;
+#[cfg(feature = "alloc")]
+pub use jump::JumpTable;
+#[cfg(feature = "alloc")]
+pub use limits::Limits;
+#[cfg(feature = "alloc")]
+pub use lines::{Columns, LineCol, LineIndex, Span};
+#[cfg(feature = "alloc")]
+pub use recover::{Recovered, Recovering, StrayPolicy};
+#[cfg(feature = "alloc")]
+pub use report::Report;
+#[cfg(feature = "alloc")]
+pub use rev::RevBlocks;
+#[cfg(feature = "alloc")]
+pub use scan::Delimiters;
+#[cfg(feature = "alloc")]
+pub use stats::Stats;
+#[cfg(feature = "std")]
+pub use stream::{ChunkedBlocks, ReadBlocksError};
+#[cfg(feature = "alloc")]
+pub use tree::{BlockTree, NodeId};
+#[cfg(feature = "alloc")]
+pub use visit::{BlockVisitor, Visit};
 
 /// Denotes a potentially unbalanced block
 pub type Unbalanced = Option<NonZeroUsize>;
@@ -8,10 +82,22 @@ pub type Unbalanced = Option<NonZeroUsize>;
 pub type Balanced = usize;
 
 mod seal {
-    pub trait Sealed {}
+    pub trait Sealed {
+        /// Retrieve the index of the closing token, if any
+        fn closing_index(self) -> Option<usize>;
+    }
     
-    impl Sealed for super::Unbalanced {}
-    impl Sealed for super::Balanced {}
+    impl Sealed for super::Unbalanced {
+        fn closing_index(self) -> Option<usize> {
+            self.map(core::num::NonZeroUsize::get)
+        }
+    }
+    
+    impl Sealed for super::Balanced {
+        fn closing_index(self) -> Option<usize> {
+            Some(self)
+        }
+    }
 }
 
 // Sealed trait to prevent `Block`'s state parameter abuse
@@ -22,33 +108,176 @@ impl BlockState for Unbalanced {}
 impl BlockState for Balanced {}
 
 /** A left-to-right block.
-    Comes in two forms: unbalanced and balanced. */
+    Comes in two forms: unbalanced and balanced.
+    Carries the kind `K` of its delimiters, `()` for untyped blocks.
+    Tokens span a single index unless created from ranges.
+    Blocks compare by their tokens and kind, ordered by their opening index first. */
 #[non_exhaustive]
 #[derive(Clone, Debug)]
-pub struct Block<T: BlockState> {
+pub struct Block<T: BlockState, K = ()> {
     opening: usize,
-    closing: T
+    closing: T,
+    kind: K,
+    opening_len: usize,
+    closing_len: usize
 }
 
 impl Block<Unbalanced> {
     /// Create an unbalanced block
     #[inline(always)]
     pub const fn open(idx: usize) -> Self {
+        Self::open_kind(idx, ())
+    }
+}
+
+impl<K> Block<Unbalanced, K> {
+    /// Create an unbalanced block of the given kind
+    #[inline(always)]
+    pub const fn open_kind(idx: usize, kind: K) -> Self {
+        Self::open_token(idx..idx + 1, kind)
+    }
+    
+    /** Create an unbalanced block of the given kind whose opening token spans `opening`.
+        Panics if `opening` is reversed */
+    #[inline(always)]
+    pub const fn open_token(opening: Range<usize>, kind: K) -> Self {
+        assert!(opening.start <= opening.end, "reversed token range");
+        
         Self {
-            opening: idx,
-            closing: None
+            opening: opening.start,
+            closing: None,
+            kind,
+            opening_len: opening.end - opening.start,
+            closing_len: 0
         }
     }
+    
+    /// Convert a block known to be closed, an open one getting a closing index of `0`
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    pub(crate) fn into_balanced(self) -> Block<Balanced, K> {
+        Block {
+            opening: self.opening,
+            closing: self.closing.map_or(0, NonZeroUsize::get),
+            kind: self.kind,
+            opening_len: self.opening_len,
+            closing_len: self.closing_len
+        }
+    }
+}
+
+impl<K> Block<Balanced, K> {
+    /// Force the creation of a block of the given kind from the ranges of its tokens
+    ///
+    /// # Safety
+    /// The caller must ensure that the `opening` token ends before the `closing` one starts
+    #[inline(always)]
+    pub const unsafe fn with_tokens_unchecked(opening: Range<usize>, closing: Range<usize>, kind: K) -> Self {
+        Self {
+            opening: opening.start,
+            closing: closing.start,
+            kind,
+            opening_len: opening.end - opening.start,
+            closing_len: closing.end - closing.start
+        }
+    }
+    
+    /// Retrieve the range of the closing token
+    #[inline(always)]
+    pub const fn closing_token(&self) -> Range<usize> {
+        self.closing..self.closing + self.closing_len
+    }
+    
+    /// Retrieve the range of the block, delimiters included
+    #[inline(always)]
+    pub const fn outer_range(&self) -> Range<usize> {
+        self.opening..self.closing + self.closing_len
+    }
+    
+    /// Retrieve the range between the delimiters
+    #[inline(always)]
+    pub const fn inner_range(&self) -> Range<usize> {
+        self.opening + self.opening_len..self.closing
+    }
+    
+    /// Retrieve the length of the block, delimiters included
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.closing + self.closing_len - self.opening
+    }
+    
+    /// Check whether the block has a length of zero, which only zero-width tokens allow
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Check whether an index lies in the block, delimiters included
+    #[inline(always)]
+    pub const fn contains(&self, idx: usize) -> bool {
+        self.opening <= idx && idx < self.closing + self.closing_len
+    }
+    
+    /// Check whether another block lies in this one, delimiters included, a block containing itself
+    #[inline(always)]
+    pub const fn contains_block<L>(&self, other: &Block<Balanced, L>) -> bool {
+        self.opening <= other.opening && other.closing + other.closing_len <= self.closing + self.closing_len
+    }
+    
+    /// Check whether another block is nested between the delimiters of this one
+    #[inline(always)]
+    pub const fn is_ancestor_of<L>(&self, other: &Block<Balanced, L>) -> bool {
+        self.opening + self.opening_len <= other.opening && other.closing + other.closing_len <= self.closing
+    }
+    
+    /** Slice the block out of a text or a slice indexed like the tokens, delimiters included.
+        Panics if the block is out of bounds */
+    #[inline(always)]
+    pub fn slice_outer<'a, S: Index<Range<usize>> + ?Sized>(&self, source: &'a S) -> &'a S::Output {
+        &source[self.outer_range()]
+    }
+    
+    /** Slice the contents of the block out of a text or a slice indexed like the tokens.
+        Panics if the block is out of bounds */
+    #[inline(always)]
+    pub fn slice_inner<'a, S: Index<Range<usize>> + ?Sized>(&self, source: &'a S) -> &'a S::Output {
+        &source[self.inner_range()]
+    }
 }
 
 impl<T: BlockState> Block<T> {
     /// Force the creation of a block
+    ///
+    /// # Safety
+    /// The caller must ensure that `opening` and `closing` describe a block
+    /// in the state denoted by `T`
     #[allow(unused_unsafe)]
     #[inline(always)]
     pub const unsafe fn new_unchecked(opening: usize, closing: T) -> Self {
         Self {
             opening,
-            closing
+            closing,
+            kind: (),
+            opening_len: 1,
+            closing_len: 1
+        }
+    }
+}
+
+impl<T: BlockState, K> Block<T, K> {
+    /// Force the creation of a block of the given kind
+    ///
+    /// # Safety
+    /// The caller must ensure that `opening` and `closing` describe a block
+    /// in the state denoted by `T`
+    #[inline(always)]
+    pub const unsafe fn with_kind_unchecked(opening: usize, closing: T, kind: K) -> Self {
+        Self {
+            opening,
+            closing,
+            kind,
+            opening_len: 1,
+            closing_len: 1
         }
     }
     
@@ -58,72 +287,329 @@ impl<T: BlockState> Block<T> {
         self.opening
     }
     
+    /// Retrieve the range of the opening token
+    #[inline(always)]
+    pub const fn opening_token(&self) -> Range<usize> {
+        self.opening..self.opening + self.opening_len
+    }
+    
     /** Retrieve the index of the closing token.
         `Option<NonZeroUsize>` if unbalanced, `usize` otherwise */
     #[inline(always)]
     pub const fn closing(&self) -> T {
         self.closing
     }
+    
+    /// Retrieve the kind of the delimiters
+    #[inline(always)]
+    pub const fn kind(&self) -> &K {
+        &self.kind
+    }
 }
 
-/// A left-to-right block processor
+impl<T: BlockState, K> Block<T, K> {
+    /// Identity of the block: its tokens and kind, the width of a missing closing token aside
+    #[inline(always)]
+    fn key(&self) -> (usize, Option<(usize, usize)>, &K, usize) {
+        let closing = self.closing.closing_index().map(|closing| (closing, self.closing_len));
+        (self.opening, closing, &self.kind, self.opening_len)
+    }
+}
+
+impl<T: BlockState, K: PartialEq> PartialEq for Block<T, K> {
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<T: BlockState, K: Eq> Eq for Block<T, K> {}
+
+impl<T: BlockState, K: Hash> Hash for Block<T, K> {
+    #[inline(always)]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl<T: BlockState, K: PartialOrd> PartialOrd for Block<T, K> {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.key().partial_cmp(&other.key())
+    }
+}
+
+impl<T: BlockState, K: Ord> Ord for Block<T, K> {
+    #[inline(always)]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(feature = "alloc")]
+/** A left-to-right block processor.
+    Closing tokens must be of the same kind `K` as the innermost open block.
+    Opening tokens are checked against optional resource `Limits`. */
 #[non_exhaustive]
 #[derive(Clone, Debug)]
-pub struct Blocks {
-    inner: Vec<Block<Unbalanced>>,
-    lhs: usize,
-    rhs: usize
+pub struct Blocks<K = ()> {
+    inner: Vec<Block<Unbalanced, K>>,
+    open: Vec<usize>,
+    limits: Limits,
+    // First error met while extending from an iterator
+    error: Option<BalanceBlockError<K>>
 }
 
+#[cfg(feature = "alloc")]
 impl Blocks {
-    /// Construct an empty `Blocks` structure
+    /// Add a new opening token to the list, checking the limits
+    #[inline(always)]
+    pub fn add_left(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
+        self.add_left_kind(idx, ())
+    }
+    
+    /// Add a new closing token to the list, closing the innermost open block
+    #[inline(always)]
+    pub fn add_right(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
+        self.add_right_kind(idx, ())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K> Blocks<K> {
+    /// Construct an empty `Blocks` structure without limits
     #[inline(always)]
     pub const fn new() -> Self {
+        Self::with_limits(Limits::new())
+    }
+    
+    /// Construct an empty `Blocks` structure with resource limits
+    #[inline(always)]
+    pub const fn with_limits(limits: Limits) -> Self {
         Self {
             inner: Vec::new(),
-            lhs: 0,
-            rhs: 0
+            open: Vec::new(),
+            limits,
+            error: None
         }
     }
     
-    /// Add a new opening token to the list
-    pub fn add_left(&mut self, idx: usize) {
-        self.inner.push(Block::open(idx));
-        self.lhs += 1;
+    /// Retrieve the resource limits
+    #[inline(always)]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
     }
     
-    /// Add a new closing token to the list
-    pub fn add_right(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
-        self.lhs -= 1;
-        self.inner
-            .get_mut(self.lhs)
-            .map(|block| {
-                block.closing = Some(NonZeroUsize::new(idx)?);
-                Some(self.rhs += 1)
-            })
-            .flatten()
-            .ok_or(BalanceBlockError::ExtraRight)
+    /// Add a new opening token of the given kind to the list, checking the limits
+    #[inline(always)]
+    pub fn add_left_kind(&mut self, idx: usize, kind: K) -> Result<(), BalanceBlockError<K>> {
+        self.add_left_token(idx..idx + 1, kind)
+    }
+    
+    /** Add a new opening token of the given kind spanning `range` to the list, checking the limits.
+        The token is not added if it is reversed or if a limit is exceeded */
+    pub fn add_left_token(&mut self, range: Range<usize>, kind: K) -> Result<(), BalanceBlockError<K>> {
+        if range.start > range.end {
+            return Err(BalanceBlockError::InvalidToken {start: range.start, end: range.end});
+        }
+        
+        let (depth, blocks) = (self.open.len() + 1, self.inner.len() + 1);
+        let memory = limits::memory::<Block<Unbalanced, K>, usize>(blocks, depth);
+        
+        self.limits.check(depth, blocks, memory, range.start)?;
+        self.limits.reserve((&mut self.inner, blocks), (&mut self.open, depth), range.start)?;
+        self.open.push(self.inner.len());
+        self.inner.push(Block::open_token(range, kind));
+        Ok(())
     }
     
     /// Check whether the tokens are balanced
     pub fn is_valid(&self) -> bool {
-        self.inner.len() == self.rhs
+        self.open.is_empty() && self.error.is_none()
+    }
+    
+    /// Iterate over the blocks that are still open, from the outermost to the innermost
+    pub fn unclosed(&self) -> impl DoubleEndedIterator<Item = &Block<Unbalanced, K>> + '_ {
+        self.open.iter().map(|&i| &self.inner[i])
+    }
+    
+    /** Check the validity of the structure and return a vector of balanced blocks.
+        The blocks are ordered by their opening index */
+    pub fn consume(mut self) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        
+        if !self.is_valid() {
+            return Err(BalanceBlockError::ExtraLeft {
+                unclosed: self.unclosed().map(Block::opening).collect()
+            });
+        }
+        
+        // Every block is closed at this point
+        Ok(self.inner.into_iter().map(Block::into_balanced).collect())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: PartialEq + Clone> Blocks<K> {
+    /** Add a new closing token of the given kind to the list,
+        closing the innermost open block */
+    #[inline(always)]
+    pub fn add_right_kind(&mut self, idx: usize, kind: K) -> Result<(), BalanceBlockError<K>> {
+        self.add_right_token(idx..idx + 1, kind)
+    }
+    
+    /** Add a new closing token of the given kind spanning `range` to the list,
+        closing the innermost open block.
+        The token must not be reversed nor start before the end of the opening token */
+    pub fn add_right_token(&mut self, range: Range<usize>, kind: K) -> Result<(), BalanceBlockError<K>> {
+        let idx = range.start;
+        
+        if range.start > range.end {
+            return Err(BalanceBlockError::InvalidToken {start: range.start, end: range.end});
+        }
+        
+        let (closing, &last) = NonZeroUsize::new(idx)
+            .zip(self.open.last())
+            .ok_or(BalanceBlockError::ExtraRight {closing: idx})?;
+        let block = &mut self.inner[last];
+        
+        if idx < block.opening_token().end {
+            return Err(BalanceBlockError::InvalidToken {start: range.start, end: range.end});
+        }
+        
+        if block.kind != kind {
+            return Err(BalanceBlockError::Mismatch {
+                expected: block.kind.clone(),
+                found: kind,
+                opening: block.opening,
+                closing: idx
+            });
+        }
+        
+        block.closing = Some(closing);
+        block.closing_len = range.len();
+        self.open.pop();
+        Ok(())
     }
     
-    /// Check the validity of the structure and return a vector of balanced blocks
-    pub fn consume(self) -> Result<Vec<Block<Balanced>>, BalanceBlockError> {
-        // SAFETY: the blocks are guaranteed to be balanced at this point
-        self.is_valid()
-            .then_some(unsafe {core::mem::transmute(self.inner)})
-            .ok_or(BalanceBlockError::ExtraLeft)
+    /// Add a new token of the given kind to the list
+    #[inline(always)]
+    pub fn add(&mut self, idx: usize, event: Event<K>) -> Result<(), BalanceBlockError<K>> {
+        self.add_token(idx..idx + 1, event)
+    }
+    
+    /// Add a new token of the given kind spanning `range` to the list
+    pub fn add_token(&mut self, range: Range<usize>, event: Event<K>) -> Result<(), BalanceBlockError<K>> {
+        match event {
+            Event::Open(kind) => self.add_left_token(range, kind),
+            Event::Close(kind) => self.add_right_token(range, kind)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K> Default for Blocks<K> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// A delimiter token of kind `K`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Event<K = ()> {
+    /// An opening token
+    Open(K),
+    /// A closing token
+    Close(K)
+}
+
 /// Balancing error
-#[derive(Clone, Copy, Debug, Error)]
-pub enum BalanceBlockError {
-    #[error("unbalanced closing token")]
-    ExtraRight,
-    #[error("unbalanced opening token")]
-    ExtraLeft,
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BalanceBlockError<K = ()> {
+    /// A closing token without an open block
+    ExtraRight {
+        /// Index of the closing token
+        closing: usize
+    },
+    /** Blocks left open at the end of the input.
+        Matched with `..`, as its fields depend on the features */
+    #[non_exhaustive]
+    ExtraLeft {
+        /// Indices of the opening tokens, from the outermost to the innermost.
+        /// Only available with the `alloc` feature
+        #[cfg(feature = "alloc")]
+        unclosed: Vec<usize>
+    },
+    /// A closing token of a kind different from the innermost open block
+    Mismatch {
+        /// Kind of the innermost open block
+        expected: K,
+        /// Kind of the closing token
+        found: K,
+        /// Index of the opening token of the innermost open block
+        opening: usize,
+        /// Index of the closing token
+        closing: usize
+    },
+    /// More blocks than a fixed-capacity structure can hold
+    CapacityExceeded {
+        /// Maximum number of blocks
+        capacity: usize
+    },
+    /// A block nested deeper than the limit
+    DepthExceeded {
+        /// Index of the token opening the block, see `MemoryExceeded::opening`
+        opening: usize,
+        /// Maximum number of blocks open at the same time
+        limit: usize
+    },
+    /// A block past the limit of blocks
+    BlocksExceeded {
+        /// Index of the token opening the block, see `MemoryExceeded::opening`
+        opening: usize,
+        /// Maximum number of blocks
+        limit: usize
+    },
+    /// A block past the limit of memory
+    MemoryExceeded {
+        /** Index of the token opening the block: the opening token left to right,
+            the closing token for `RevBlocks`, and any token running out of the memory shared by a parallel scan */
+        opening: usize,
+        /// Maximum memory use in bytes
+        limit: usize
+    },
+    /// A reversed token range, or a closing token starting before the end of the opening one
+    InvalidToken {
+        /// Start of the token
+        start: usize,
+        /// End of the token
+        end: usize
+    },
+}
+
+impl<K: Debug> Display for BalanceBlockError<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExtraRight {closing} => write!(f, "unbalanced closing token at {closing}"),
+            #[cfg(feature = "alloc")]
+            Self::ExtraLeft {unclosed} => write!(f, "unbalanced opening tokens at {unclosed:?}"),
+            #[cfg(not(feature = "alloc"))]
+            Self::ExtraLeft {} => write!(f, "unbalanced opening tokens"),
+            Self::Mismatch {expected, found, opening, closing} => write!(
+                f,
+                "mismatched closing token at {closing}: expected {expected:?} opened at {opening}, found {found:?}"
+            ),
+            Self::CapacityExceeded {capacity} => write!(f, "exceeded the capacity of {capacity} blocks"),
+            Self::DepthExceeded {opening, limit} => write!(f, "token at {opening} exceeds the depth limit of {limit}"),
+            Self::BlocksExceeded {opening, limit} => write!(f, "token at {opening} exceeds the limit of {limit} blocks"),
+            Self::MemoryExceeded {opening, limit} => write!(f, "token at {opening} exceeds the memory limit of {limit} bytes"),
+            Self::InvalidToken {start, end} => write!(f, "invalid token range {start}..{end}"),
+        }
+    }
 }
+
+impl<K: Debug> core::error::Error for BalanceBlockError<K> {}
@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+use core::mem::size_of;
+use crate::BalanceBlockError;
+
+/** Resource limits of a `Blocks` structure, checked whenever a block is opened.
+    Nothing is limited by default. */
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+    depth: Option<usize>,
+    blocks: Option<usize>,
+    memory: Option<usize>
+}
+
+impl Limits {
+    /// Construct a set of limits that limits nothing
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            depth: None,
+            blocks: None,
+            memory: None
+        }
+    }
+    
+    /** Limit the number of blocks open at the same time, a top-level block counting as `1`.
+        Blocks with a `Stats::max_depth` of `d`, counted from `0`, fit within a limit of `d + 1` */
+    #[inline(always)]
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+    
+    /// Limit the total number of blocks
+    #[inline(always)]
+    pub const fn max_blocks(mut self, blocks: usize) -> Self {
+        self.blocks = Some(blocks);
+        self
+    }
+    
+    /** Limit the memory used by the blocks and the stack of open blocks, in bytes.
+        Spare capacity of the underlying vectors is accounted for, and given back before growing past the limit */
+    #[inline(always)]
+    pub const fn max_memory(mut self, bytes: usize) -> Self {
+        self.memory = Some(bytes);
+        self
+    }
+    
+    /// Lift the memory limit, for the chunks of a parallel scan sharing it
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    pub(crate) const fn unlimited_memory(mut self) -> Self {
+        self.memory = None;
+        self
+    }
+    
+    /// Retrieve the maximum nesting depth, if any
+    #[inline(always)]
+    pub const fn depth(&self) -> Option<usize> {
+        self.depth
+    }
+    
+    /// Retrieve the maximum number of blocks, if any
+    #[inline(always)]
+    pub const fn blocks(&self) -> Option<usize> {
+        self.blocks
+    }
+    
+    /// Retrieve the maximum memory use in bytes, if any
+    #[inline(always)]
+    pub const fn memory(&self) -> Option<usize> {
+        self.memory
+    }
+    
+    /** Check that `depth` open blocks out of `blocks`, taking `memory` bytes, fit in the limits,
+        blaming the token at `opening` */
+    pub(crate) fn check<K>(&self, depth: usize, blocks: usize, memory: usize, opening: usize) -> Result<(), BalanceBlockError<K>> {
+        match (self.depth, self.blocks, self.memory) {
+            (Some(limit), _, _) if depth > limit => Err(BalanceBlockError::DepthExceeded {opening, limit}),
+            (_, Some(limit), _) if blocks > limit => Err(BalanceBlockError::BlocksExceeded {opening, limit}),
+            (_, _, Some(limit)) if memory > limit => Err(BalanceBlockError::MemoryExceeded {opening, limit}),
+            _ => Ok(())
+        }
+    }
+    
+    /** Make room for `a_len` and `b_len` elements in two vectors without their capacity going past the memory limit.
+        Must follow a successful `check` of the same lengths, which guarantees that they fit once spare capacity is given back */
+    pub(crate) fn reserve<A, B, K>(
+        &self,
+        (a, a_len): (&mut Vec<A>, usize),
+        (b, b_len): (&mut Vec<B>, usize),
+        opening: usize
+    ) -> Result<(), BalanceBlockError<K>> {
+        let Some(limit) = self.memory else {
+            return Ok(());
+        };
+        
+        let spare = |a: &Vec<A>, b: &Vec<B>| limit.saturating_sub(memory::<A, B>(a.capacity(), b.capacity()));
+        
+        for attempt in 0..2 {
+            if attempt > 0 {
+                a.shrink_to(a_len);
+                b.shrink_to(b_len);
+            }
+            
+            if grow(a, a_len, spare(a, b)) && grow(b, b_len, spare(a, b)) {
+                return Ok(());
+            }
+        }
+        
+        Err(BalanceBlockError::MemoryExceeded {opening, limit})
+    }
+}
+
+/// Memory taken by `a` elements of type `A` and `b` elements of type `B`
+#[inline(always)]
+pub(crate) const fn memory<A, B>(a: usize, b: usize) -> usize {
+    a * size_of::<A>() + b * size_of::<B>()
+}
+
+/** Make room for `len` elements in `vec`, at least doubling its capacity if `spare` bytes allow.
+    Returns `false` if they do not fit */
+pub(crate) fn grow<T>(vec: &mut Vec<T>, len: usize, spare: usize) -> bool {
+    if len <= vec.capacity() {
+        return true;
+    }
+    
+    let fits = vec.capacity() + spare / size_of::<T>().max(1);
+    let capacity = (2 * vec.capacity()).max(len).max(4).min(fits);
+    
+    capacity >= len && vec.try_reserve_exact(capacity - vec.len()).is_ok()
+}
@@ -0,0 +1,190 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Range;
+use crate::{Balanced, Block, BlockState};
+
+/// Unit in which columns are counted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Columns {
+    /// Bytes of UTF-8
+    Utf8,
+    /// Code units of UTF-16, as in the Language Server Protocol
+    Utf16,
+    /// Unicode scalar values
+    #[default]
+    Char
+}
+
+/// A 1-based line and column position
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Column number, starting at 1
+    pub col: usize
+}
+
+impl LineCol {
+    /// Construct a position from a 1-based line and column
+    #[inline(always)]
+    pub const fn new(line: usize, col: usize) -> Self {
+        Self {line, col}
+    }
+}
+
+impl Display for LineCol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// A range of text between two positions, the end being exclusive
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    /// Position of the first character
+    pub start: LineCol,
+    /// Position right after the last character
+    pub end: LineCol
+}
+
+impl Span {
+    /// Construct a span from its bounds
+    #[inline(always)]
+    pub const fn new(start: LineCol, end: LineCol) -> Self {
+        Self {start, end}
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/** Converts byte offsets of a text into line and column positions.
+    Lines end with `'\n'`; a tab advances the column to the next multiple of the tab width. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    // Byte offset of the start of every line
+    starts: Vec<usize>,
+    tab_width: usize,
+    columns: Columns
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the lines of a text, counting columns in chars with a tab width of 1
+    pub fn new(text: &'a str) -> Self {
+        let starts = core::iter::once(0)
+            .chain(text.bytes().enumerate().filter(|&(_, b)| b == b'\n').map(|(n, _)| n + 1))
+            .collect();
+        
+        Self {
+            text,
+            starts,
+            tab_width: 1,
+            columns: Columns::Char
+        }
+    }
+    
+    /** Set the number of columns between tab stops.
+        Panics if `width` is zero */
+    pub fn tab_width(mut self, width: usize) -> Self {
+        assert!(width > 0, "zero tab width");
+        
+        self.tab_width = width;
+        self
+    }
+    
+    /// Set the unit in which columns are counted
+    pub fn columns(mut self, columns: Columns) -> Self {
+        self.columns = columns;
+        self
+    }
+    
+    /// Retrieve the indexed text
+    #[inline(always)]
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+    
+    /// Retrieve the number of lines, an empty text having one
+    #[inline(always)]
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+    
+    /// Retrieve the byte range of a 1-based line, without its line break
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self.starts.get(line).map_or(self.text.len(), |next| next - 1);
+        
+        Some(start..end)
+    }
+    
+    /// Retrieve the text of a 1-based line, without its line break
+    #[inline(always)]
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        self.line_range(line).map(|range| &self.text[range])
+    }
+    
+    /** Convert a byte offset into a position.
+        An offset inside a multi-byte char counts the whole char.
+        Panics if `offset` is past the end of the text */
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        assert!(offset <= self.text.len(), "offset {offset} out of bounds");
+        
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let start = self.starts[line - 1];
+        let col = self.text.as_bytes()[start..offset].iter().fold(0, |col, &byte| match byte {
+            b'\t' => (col / self.tab_width + 1) * self.tab_width,
+            _ => col + self.width(byte)
+        });
+        
+        LineCol::new(line, col + 1)
+    }
+    
+    /// Convert a byte range into a span
+    #[inline(always)]
+    pub fn span(&self, range: Range<usize>) -> Span {
+        Span::new(self.line_col(range.start), self.line_col(range.end))
+    }
+    
+    /// Number of columns accounted to a byte, charging a whole char to its leading byte
+    #[inline(always)]
+    fn width(&self, byte: u8) -> usize {
+        match (self.columns, byte) {
+            (Columns::Utf8, _) => 1,
+            // Continuation bytes
+            (_, 0x80..=0xbf) => 0,
+            // Chars outside of the basic multilingual plane take a surrogate pair
+            (Columns::Utf16, 0xf0..) => 2,
+            _ => 1
+        }
+    }
+}
+
+impl<T: BlockState, K> Block<T, K> {
+    /// Retrieve the span of the opening token, its indices being byte offsets into the indexed text
+    #[inline(always)]
+    pub fn opening_span(&self, index: &LineIndex<'_>) -> Span {
+        index.span(self.opening_token())
+    }
+}
+
+impl<K> Block<Balanced, K> {
+    /// Retrieve the span of the closing token, its indices being byte offsets into the indexed text
+    #[inline(always)]
+    pub fn closing_span(&self, index: &LineIndex<'_>) -> Span {
+        index.span(self.closing_token())
+    }
+    
+    /// Retrieve the span from the start of the opening token to the end of the closing one
+    #[inline(always)]
+    pub fn span(&self, index: &LineIndex<'_>) -> Span {
+        index.span(self.outer_range())
+    }
+}
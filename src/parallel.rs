@@ -0,0 +1,307 @@
+use core::mem::{self, size_of};
+use core::num::NonZeroUsize;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::vec::Vec;
+use crate::limits::{self, Limits};
+use crate::{BalanceBlockError, Balanced, Block, ByteDelimiters, Blocks, Event, Unbalanced};
+
+/// Length under which a chunk is not worth a thread of its own
+const MIN_CHUNK: usize = 4096;
+
+/** The outcome of matching one chunk on its own.
+    Closing tokens left without an open block all precede the blocks left open,
+    which are the ones recorded by `blocks.open` */
+struct Part<'a, K> {
+    blocks: Blocks<K>,
+    strays: Vec<(usize, K)>,
+    // Bytes left to the capacity of the vectors of every part and the memory limit, if any
+    budget: Option<(&'a AtomicUsize, usize)>,
+    error: Option<BalanceBlockError<K>>
+}
+
+impl<'a, K: PartialEq + Clone> Part<'a, K> {
+    /// Construct an empty part, checking the depth and the number of blocks of the chunk on its own
+    #[inline(always)]
+    fn new(limits: Limits, budget: Option<(&'a AtomicUsize, usize)>) -> Self {
+        Self {
+            blocks: Blocks::with_limits(limits.unlimited_memory()),
+            strays: Vec::new(),
+            budget,
+            error: None
+        }
+    }
+    
+    /// Add a token, ignoring everything after the first local error
+    fn add(&mut self, idx: usize, event: Event<K>) {
+        if self.error.is_some() {
+            return;
+        }
+        
+        let budget = self.budget.map(|(budget, _)| budget);
+        let fits = match event {
+            Event::Open(_) => {
+                claim(&mut self.blocks.inner, budget) && claim(&mut self.blocks.open, budget)
+            }
+            Event::Close(_) if self.blocks.is_valid() => claim(&mut self.strays, budget),
+            Event::Close(_) => true
+        };
+        
+        if let (false, Some((_, limit))) = (fits, self.budget) {
+            self.error = Some(BalanceBlockError::MemoryExceeded {opening: idx, limit});
+            return;
+        }
+        
+        match event {
+            Event::Open(kind) => self.error = self.blocks.add_left_kind(idx, kind).err(),
+            // The enclosing block, if any, was opened by an earlier chunk
+            Event::Close(kind) if self.blocks.is_valid() => self.strays.push((idx, kind)),
+            Event::Close(kind) => self.error = self.blocks.add_right_kind(idx, kind).err()
+        }
+    }
+    
+    /** Find the first opening token past the limits, the blocks of the earlier parts included,
+        `depth` blocks being open and `count` blocks having been opened before the part */
+    fn exceeded(&self, limits: &Limits, depth: usize, count: usize) -> Option<(usize, BalanceBlockError<K>)> {
+        if *limits == Limits::new() {
+            return None;
+        }
+        
+        // An opening token past the local limits was not added, running out of the shared memory stops the part instead
+        let failed = match self.error {
+            Some(BalanceBlockError::DepthExceeded {opening, ..} | BalanceBlockError::BlocksExceeded {opening, ..}) => Some((opening, None)),
+            _ => None
+        };
+        
+        // Closing indices of the enclosing blocks of the part, `None` if left open
+        let mut stack: Vec<Option<usize>> = Vec::new();
+        let mut strays = self.strays.iter().peekable();
+        let mut closed = 0;
+        
+        self.blocks.inner
+            .iter()
+            .map(|block| (block.opening, block.closing.map(NonZeroUsize::get)))
+            .chain(failed)
+            .enumerate()
+            .find_map(|(n, (opening, closing))| {
+                while stack.last().is_some_and(|end| end.is_some_and(|end| end < opening)) {
+                    stack.pop();
+                }
+                
+                // Every stray before the token closes a block of the earlier parts
+                while strays.next_if(|&&(stray, _)| stray < opening).is_some() {
+                    closed += 1;
+                }
+                
+                let depth = (depth + stack.len() + 1).saturating_sub(closed);
+                let blocks = count + n + 1;
+                let memory = limits::memory::<Block<Unbalanced, K>, usize>(blocks, depth);
+                
+                stack.push(closing);
+                limits.check(depth, blocks, memory, opening).err().map(|err| (opening, err))
+            })
+    }
+}
+
+/** Make room for one more element in `vec`, taking the growth of its capacity from the shared `budget`, if any.
+    Returns `false` if the budget is spent */
+fn claim<T>(vec: &mut Vec<T>, budget: Option<&AtomicUsize>) -> bool {
+    let len = vec.len() + 1;
+    let Some(budget) = budget.filter(|_| len > vec.capacity()) else {
+        return true;
+    };
+    
+    let size = size_of::<T>().max(1);
+    let mut claimed = 0;
+    let result = budget.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |spare| {
+        let capacity = (2 * vec.capacity()).max(len).max(4).min(vec.capacity() + spare / size);
+        
+        claimed = capacity.saturating_sub(vec.capacity()) * size;
+        (capacity >= len).then(|| spare - claimed)
+    });
+    
+    result.is_ok() && limits::grow(vec, len, claimed)
+}
+
+/// Match every chunk of `bytes` on its own thread and merge the parts
+fn balance<K, G>(bytes: &[u8], size: usize, limits: Limits, find: G) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>>
+where
+    K: PartialEq + Clone + Send,
+    G: Fn(&[u8], usize, &mut Part<K>) + Sync
+{
+    let budget = limits.memory().map(AtomicUsize::new);
+    let budget = budget.as_ref().zip(limits.memory());
+    let mut parts: Vec<Part<K>> = thread::scope(|scope| {
+        let handles: Vec<_> = bytes
+            .chunks(size)
+            .enumerate()
+            .map(|(n, chunk)| {
+                let find = &find;
+                
+                scope.spawn(move || {
+                    let mut part = Part::new(limits, budget);
+                    find(chunk, n * size, &mut part);
+                    part
+                })
+            })
+            .collect();
+        
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+    
+    // Parts whose blocks are left open so far, the innermost last, so that no extra stack is allocated
+    let mut open: Vec<usize> = Vec::with_capacity(parts.len());
+    let (mut depth, mut count) = (0, 0);
+    
+    // Errors are met in the same order as a sequential scan would
+    for p in 0..parts.len() {
+        // Limits of a part only bound the chunk, the blocks of the earlier parts add to it
+        let mut exceeded = parts[p].exceeded(&limits, depth, count);
+        
+        for (closing, kind) in mem::take(&mut parts[p].strays) {
+            if let Some((_, err)) = exceeded.take_if(|&mut (opening, _)| opening < closing) {
+                return Err(err);
+            }
+            
+            let q = *open.last().ok_or(BalanceBlockError::ExtraRight {closing})?;
+            let i = parts[q].blocks.open.pop().expect("parts on the stack have open blocks");
+            depth -= 1;
+            
+            if parts[q].blocks.open.is_empty() {
+                open.pop();
+            }
+            
+            let block = &mut parts[q].blocks.inner[i];
+            
+            if block.kind != kind {
+                return Err(BalanceBlockError::Mismatch {
+                    expected: block.kind.clone(),
+                    found: kind,
+                    opening: block.opening,
+                    closing
+                });
+            }
+            
+            // The closing token follows the opening one
+            block.closing = NonZeroUsize::new(closing);
+            block.closing_len = 1;
+        }
+        
+        // A part stops at a local error, after any opening token past the limits
+        if let Some(err) = exceeded.map(|(_, err)| err).or(parts[p].error.take()) {
+            return Err(err);
+        }
+        
+        count += parts[p].blocks.inner.len();
+        depth += parts[p].blocks.open.len();
+        
+        if !parts[p].blocks.open.is_empty() {
+            open.push(p);
+        }
+    }
+    
+    if depth != 0 {
+        return Err(BalanceBlockError::ExtraLeft {
+            unclosed: open.into_iter().flat_map(|p| {
+                let blocks = &parts[p].blocks;
+                blocks.open.iter().map(|&i| blocks.inner[i].opening)
+            }).collect()
+        });
+    }
+    
+    // Parts cover increasing ranges, so the blocks stay ordered by their opening index
+    let mut blocks = Vec::with_capacity(count);
+    
+    for part in parts {
+        blocks.extend(part.blocks.inner.into_iter().map(Block::into_balanced));
+    }
+    
+    Ok(blocks)
+}
+
+/// Length of the chunks to split `len` bytes into, `None` if a single one would hold them
+#[inline(always)]
+fn chunk_size(len: usize, threads: NonZeroUsize) -> Option<usize> {
+    let size = len.div_ceil(threads.get()).max(MIN_CHUNK);
+    (len > size).then_some(size)
+}
+
+impl<K: PartialEq + Clone + Send> Blocks<K> {
+    /** Balance a buffer split into `threads` chunks, each matched on its own thread.
+        Bytes are classified into tokens by `classify`.
+        Buffers too short to be worth splitting are balanced on the calling thread.
+        Yields the same blocks and the same first error as feeding every token to `Blocks` in order */
+    #[inline(always)]
+    pub fn balance_parallel<F>(bytes: &[u8], threads: NonZeroUsize, classify: F) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>>
+    where
+        F: Fn(u8) -> Option<Event<K>> + Sync
+    {
+        Self::balance_parallel_with_limits(bytes, threads, Limits::new(), classify)
+    }
+    
+    /** Balance a buffer split into `threads` chunks like `balance_parallel`, within resource limits.
+        Yields the same first error as feeding every token to `Blocks::with_limits` in order,
+        except that the chunks share the memory limit and may run out of it sooner */
+    pub fn balance_parallel_with_limits<F>(
+        bytes: &[u8],
+        threads: NonZeroUsize,
+        limits: Limits,
+        classify: F
+    ) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>>
+    where
+        F: Fn(u8) -> Option<Event<K>> + Sync
+    {
+        let Some(size) = chunk_size(bytes.len(), threads) else {
+            let mut blocks = Self::with_limits(limits);
+            
+            for (n, &byte) in bytes.iter().enumerate() {
+                if let Some(event) = classify(byte) {
+                    blocks.add(n, event)?;
+                }
+            }
+            
+            return blocks.consume();
+        };
+        
+        balance(bytes, size, limits, |chunk, offset, part| {
+            for (n, &byte) in chunk.iter().enumerate() {
+                if let Some(event) = classify(byte) {
+                    part.add(offset + n, event);
+                }
+            }
+        })
+    }
+}
+
+impl Blocks<usize> {
+    /** Balance the delimiter bytes of a buffer split into `threads` chunks,
+        each searched with vector instructions on its own thread.
+        Yields the same result as `Blocks::scan_bytes` */
+    #[inline(always)]
+    pub fn scan_bytes_parallel(bytes: &[u8], delimiters: &ByteDelimiters, threads: NonZeroUsize) -> Result<Vec<Block<Balanced, usize>>, BalanceBlockError<usize>> {
+        Self::scan_bytes_parallel_with_limits(bytes, delimiters, threads, Limits::new())
+    }
+    
+    /** Balance the delimiter bytes of a buffer like `scan_bytes_parallel`, within resource limits.
+        The chunks share the memory limit like in `balance_parallel_with_limits` */
+    pub fn scan_bytes_parallel_with_limits(
+        bytes: &[u8],
+        delimiters: &ByteDelimiters,
+        threads: NonZeroUsize,
+        limits: Limits
+    ) -> Result<Vec<Block<Balanced, usize>>, BalanceBlockError<usize>> {
+        let Some(size) = chunk_size(bytes.len(), threads) else {
+            let mut blocks = Self::with_limits(limits);
+            
+            delimiters.try_find(bytes, |idx, event| blocks.add(idx, event))?;
+            return blocks.consume();
+        };
+        
+        balance(bytes, size, limits, |chunk, offset, part| {
+            delimiters.find(chunk, |idx, event| part.add(offset + idx, event))
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use alloc::vec::Vec;
+use core::num::NonZeroUsize;
+use core::ops::Range;
+use crate::{BalanceBlockError, Balanced, Block, Blocks, Limits};
+
+/// Handling of closing tokens without an open block of their kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StrayPolicy {
+    /** Record a `BalanceBlockError::Mismatch` error against the innermost open block,
+        or `BalanceBlockError::ExtraRight` if there is none */
+    #[default]
+    Error,
+    /// Drop the token silently
+    Ignore,
+    /// Keep the token as a literal, see `Recovered::literals`
+    Literal
+}
+
+/** A left-to-right block processor that never stops at the first error.
+    Errors are collected and processing continues as if the offending token was fixed. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct Recovering<K = ()> {
+    blocks: Blocks<K>,
+    policy: StrayPolicy,
+    errors: Vec<BalanceBlockError<K>>,
+    literals: Vec<usize>
+}
+
+/// The outcome of a recovering run
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct Recovered<K = ()> {
+    /// Well-formed blocks, ordered by their opening index
+    pub blocks: Vec<Block<Balanced, K>>,
+    /// Every error encountered, in input order
+    pub errors: Vec<BalanceBlockError<K>>,
+    /// Indices of stray closing tokens kept under `StrayPolicy::Literal`
+    pub literals: Vec<usize>
+}
+
+impl<K> Recovered<K> {
+    /// Check whether the input was free of errors
+    #[inline(always)]
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Recovering {
+    /// Add a new opening token to the list
+    #[inline(always)]
+    pub fn add_left(&mut self, idx: usize) {
+        self.add_left_kind(idx, ())
+    }
+    
+    /// Add a new closing token to the list
+    #[inline(always)]
+    pub fn add_right(&mut self, idx: usize) {
+        self.add_right_kind(idx, ())
+    }
+}
+
+impl<K> Recovering<K> {
+    /// Construct an empty `Recovering` structure with a policy for stray closing tokens
+    #[inline(always)]
+    pub const fn new(policy: StrayPolicy) -> Self {
+        Self::with_limits(policy, Limits::new())
+    }
+    
+    /** Construct an empty `Recovering` structure with a policy for stray closing tokens and resource limits.
+        An opening token past the limits is recorded as an error and skipped */
+    #[inline(always)]
+    pub const fn with_limits(policy: StrayPolicy, limits: Limits) -> Self {
+        Self {
+            blocks: Blocks::with_limits(limits),
+            policy,
+            errors: Vec::new(),
+            literals: Vec::new()
+        }
+    }
+    
+    /// Add a new opening token of the given kind to the list
+    #[inline(always)]
+    pub fn add_left_kind(&mut self, idx: usize, kind: K) {
+        self.add_left_token(idx..idx + 1, kind)
+    }
+    
+    /// Add a new opening token of the given kind spanning `range` to the list
+    #[inline(always)]
+    pub fn add_left_token(&mut self, range: Range<usize>, kind: K) {
+        if let Err(err) = self.blocks.add_left_token(range, kind) {
+            self.errors.push(err);
+        }
+    }
+    
+    /// Retrieve the resource limits
+    #[inline(always)]
+    pub fn limits(&self) -> &Limits {
+        self.blocks.limits()
+    }
+    
+    /// Retrieve the errors encountered so far
+    #[inline(always)]
+    pub fn errors(&self) -> &[BalanceBlockError<K>] {
+        &self.errors
+    }
+    
+    /** Finish processing, reporting the blocks left open and returning
+        every well-formed block next to the errors */
+    pub fn finish(mut self) -> Recovered<K> {
+        if !self.blocks.is_valid() {
+            let unclosed = self.blocks.unclosed().map(Block::opening).collect();
+            self.errors.push(BalanceBlockError::ExtraLeft {unclosed});
+        }
+        
+        // Blocks abandoned during recovery are never closed
+        let blocks = self.blocks.inner
+            .into_iter()
+            .filter_map(|block| Some(Block {
+                opening: block.opening,
+                closing: block.closing?.get(),
+                kind: block.kind,
+                opening_len: block.opening_len,
+                closing_len: block.closing_len
+            }))
+            .collect();
+        
+        Recovered {
+            blocks,
+            errors: self.errors,
+            literals: self.literals
+        }
+    }
+    
+    /// Handle a closing token matching no open block according to the policy, `err` being its error
+    fn stray(&mut self, idx: usize, err: BalanceBlockError<K>) {
+        match self.policy {
+            StrayPolicy::Error => self.errors.push(err),
+            StrayPolicy::Ignore => {}
+            StrayPolicy::Literal => self.literals.push(idx)
+        }
+    }
+}
+
+impl<K: PartialEq + Clone> Recovering<K> {
+    /// Add a new closing token of the given kind to the list
+    #[inline(always)]
+    pub fn add_right_kind(&mut self, idx: usize, kind: K) {
+        self.add_right_token(idx..idx + 1, kind)
+    }
+    
+    /** Add a new closing token of the given kind spanning `range` to the list.
+        A closing token that matches an enclosing block rather than the innermost one
+        closes that block, abandoning the blocks opened in between.
+        A closing token that matches no open block is handled according to the `StrayPolicy`.
+        A reversed token, or one starting before the end of the innermost opening token, is recorded and skipped. */
+    pub fn add_right_token(&mut self, range: Range<usize>, kind: K) {
+        let idx = range.start;
+        let blocks = &mut self.blocks;
+        let invalid = BalanceBlockError::InvalidToken {start: range.start, end: range.end};
+        
+        if range.start > range.end {
+            return self.errors.push(invalid);
+        }
+        
+        let (Some(closing), Some(&innermost)) = (NonZeroUsize::new(idx), blocks.open.last()) else {
+            return self.stray(idx, BalanceBlockError::ExtraRight {closing: idx});
+        };
+        
+        let target = blocks.open
+            .iter()
+            .rposition(|&i| blocks.inner[i].kind == kind);
+        let innermost = &blocks.inner[innermost];
+        
+        if idx < innermost.opening_token().end {
+            return self.errors.push(invalid);
+        }
+        
+        if innermost.kind != kind {
+            let err = BalanceBlockError::Mismatch {
+                expected: innermost.kind.clone(),
+                found: kind,
+                opening: innermost.opening,
+                closing: idx
+            };
+            
+            if target.is_none() {
+                return self.stray(idx, err);
+            }
+            
+            self.errors.push(err);
+        }
+        
+        let Some(target) = target else {
+            return;
+        };
+        
+        // The innermost block is covered by the mismatch, the others are reported as unclosed
+        let abandoned: Vec<_> = blocks.open
+            .drain(target + 1..)
+            .map(|i| blocks.inner[i].opening)
+            .collect();
+        
+        if let [unclosed @ .., _] = &abandoned[..] {
+            if !unclosed.is_empty() {
+                self.errors.push(BalanceBlockError::ExtraLeft {unclosed: unclosed.to_vec()});
+            }
+        }
+        
+        let last = blocks.open.pop().expect("target block is open");
+        blocks.inner[last].closing = Some(closing);
+        blocks.inner[last].closing_len = range.len();
+    }
+}
+
+impl<K> Default for Recovering<K> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new(StrayPolicy::default())
+    }
+}
@@ -0,0 +1,202 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use crate::scan::is_word;
+use crate::{BalanceBlockError, Delimiters, LineIndex};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/** A compiler-style rendering of a `BalanceBlockError`, displayed as a snippet of the source
+    with a caret under the offending token and labels at the related opening tokens. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct Report<'a, K = ()> {
+    error: &'a BalanceBlockError<K>,
+    index: &'a LineIndex<'a>,
+    delimiters: Option<&'a Delimiters>,
+    path: Option<&'a str>,
+    color: bool
+}
+
+/// A message attached to a token
+struct Label {
+    offset: usize,
+    primary: bool,
+    message: String
+}
+
+impl<K> BalanceBlockError<K> {
+    /** Render the error over the text of `index`, in plain text.
+        The indices of the error must be byte offsets into that text, those past its end are left out */
+    #[inline(always)]
+    pub fn report<'a>(&'a self, index: &'a LineIndex<'a>) -> Report<'a, K> {
+        Report {
+            error: self,
+            index,
+            delimiters: None,
+            path: None,
+            color: false
+        }
+    }
+}
+
+impl<'a, K> Report<'a, K> {
+    /** Measure the tokens with the delimiters that produced the error.
+        Without them, a token is a run of word characters or a single character */
+    pub fn delimiters(mut self, delimiters: &'a Delimiters) -> Self {
+        self.delimiters = Some(delimiters);
+        self
+    }
+    
+    /// Set the path of the source shown next to the position
+    pub fn path(mut self, path: &'a str) -> Self {
+        self.path = Some(path);
+        self
+    }
+    
+    /// Choose between ANSI colour and plain text
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+    
+    /// Text of the token at `offset`
+    fn token(&self, offset: usize) -> &'a str {
+        let text = self.index.text().get(offset..).unwrap_or_default();
+        let word = text.find(|c| !is_word(c)).unwrap_or(text.len());
+        let len = self.delimiters
+            .and_then(|delimiters| delimiters.classify(text))
+            .map(|(_, len)| len)
+            .or((word > 0).then_some(word))
+            .or(text.chars().next().map(char::len_utf8))
+            .unwrap_or(0);
+        
+        &text[..len]
+    }
+    
+    /// Summary of the error, without positions
+    fn headline(&self) -> String {
+        match self.error {
+            BalanceBlockError::ExtraRight {..} => "unbalanced closing token".into(),
+            BalanceBlockError::ExtraLeft {unclosed} if unclosed.len() == 1 => "unclosed block".into(),
+            BalanceBlockError::ExtraLeft {unclosed} => format!("{} unclosed blocks", unclosed.len()),
+            BalanceBlockError::Mismatch {..} => "mismatched closing token".into(),
+            BalanceBlockError::CapacityExceeded {capacity} => format!("exceeded the capacity of {capacity} blocks"),
+            BalanceBlockError::DepthExceeded {limit, ..} => format!("exceeded the depth limit of {limit}"),
+            BalanceBlockError::BlocksExceeded {limit, ..} => format!("exceeded the limit of {limit} blocks"),
+            BalanceBlockError::MemoryExceeded {limit, ..} => format!("exceeded the memory limit of {limit} bytes"),
+            BalanceBlockError::InvalidToken {start, end} => format!("invalid token range {start}..{end}"),
+        }
+    }
+    
+    /// Labels in order of their offsets, the primary one marking the offending token
+    fn labels(&self) -> Vec<Label> {
+        let label = |offset, primary, message: &str| Label {offset, primary, message: message.into()};
+        
+        match self.error {
+            BalanceBlockError::ExtraRight {closing} => Vec::from([label(*closing, true, "no open block to close")]),
+            BalanceBlockError::ExtraLeft {unclosed} => unclosed
+                .iter()
+                .enumerate()
+                .map(|(n, &opening)| match n + 1 == unclosed.len() {
+                    true => label(opening, true, "this block is never closed"),
+                    false => label(opening, false, "enclosing block is never closed either")
+                })
+                .collect(),
+            BalanceBlockError::Mismatch {opening, closing, ..} => {
+                let (open, close) = (self.token(*opening), self.token(*closing));
+                
+                Vec::from([
+                    label(*opening, false, &format!("block opened by `{open}` here")),
+                    label(*closing, true, &format!("`{close}` does not close `{open}`"))
+                ])
+            }
+            BalanceBlockError::CapacityExceeded {..} => Vec::new(),
+            BalanceBlockError::DepthExceeded {opening, ..}
+            | BalanceBlockError::BlocksExceeded {opening, ..}
+            | BalanceBlockError::MemoryExceeded {opening, ..} => Vec::from([label(*opening, true, "limit exceeded by this block")]),
+            BalanceBlockError::InvalidToken {start, ..} => Vec::from([label(*start, true, "token reversed or overlapping its opening token")]),
+        }
+    }
+    
+    /// Write `text` in the given style, if colour is enabled
+    fn paint(&self, f: &mut Formatter<'_>, style: &str, text: impl Display) -> fmt::Result {
+        match self.color {
+            true => write!(f, "{style}{text}{RESET}"),
+            false => write!(f, "{text}")
+        }
+    }
+}
+
+impl<K> Display for Report<'_, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.paint(f, RED, "error")?;
+        self.paint(f, BOLD, format_args!(": {}", self.headline()))?;
+        writeln!(f)?;
+        
+        // Offsets past the end of the text cannot be shown, like those of another text or of chars
+        let mut labels = self.labels();
+        labels.retain(|label| label.offset <= self.index.text().len());
+        
+        let Some(primary) = labels.iter().find(|label| label.primary) else {
+            return Ok(());
+        };
+        
+        let line = |label: &Label| self.index.line_col(label.offset).line;
+        let width = labels.iter().map(line).max().unwrap_or(1).ilog10() as usize + 1;
+        let gutter = |f: &mut Formatter<'_>, line: Option<usize>| match line {
+            Some(line) => self.paint(f, BLUE, format_args!("{line:>width$} |")),
+            None => self.paint(f, BLUE, format_args!("{:width$} |", ""))
+        };
+        
+        self.paint(f, BLUE, format_args!("{:width$}--> ", ""))?;
+        if let Some(path) = self.path {
+            write!(f, "{path}:")?;
+        }
+        writeln!(f, "{}", self.index.line_col(primary.offset))?;
+        gutter(f, None)?;
+        writeln!(f)?;
+        
+        let mut previous = None;
+        
+        for label in &labels {
+            let number = line(label);
+            let range = self.index.line_range(number).unwrap_or_default();
+            
+            if previous != Some(number) {
+                if previous.is_some_and(|previous| number > previous + 1) {
+                    self.paint(f, BLUE, "...")?;
+                    writeln!(f)?;
+                }
+                
+                gutter(f, Some(number))?;
+                writeln!(f, " {}", &self.index.text()[range.clone()])?;
+                previous = Some(number);
+            }
+            
+            // Keep tabs so that the marker lines up with the token whatever the tab width
+            let indent: String = self.index.text()
+                .get(range.start..label.offset)
+                .unwrap_or_default()
+                .chars()
+                .map(|c| if c == '\t' {'\t'} else {' '})
+                .collect();
+            let (style, marker) = match label.primary {
+                true => (RED, "^"),
+                false => (BLUE, "-")
+            };
+            let marker = marker.repeat(self.token(label.offset).chars().count().max(1));
+            
+            gutter(f, None)?;
+            write!(f, " {indent}")?;
+            self.paint(f, style, format_args!("{marker} {}", label.message))?;
+            writeln!(f)?;
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+use crate::limits::{self, Limits};
+use crate::{BalanceBlockError, Balanced, Block};
+
+/** A right-to-left block processor.
+    Takes tokens from the end of the input and yields the same blocks as `Blocks`. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct RevBlocks<K = ()> {
+    inner: Vec<Block<Balanced, K>>,
+    pending: Vec<(Range<usize>, K)>,
+    limits: Limits
+}
+
+impl RevBlocks {
+    /// Add a new closing token to the list, checking the limits
+    #[inline(always)]
+    pub fn add_right(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
+        self.add_right_kind(idx, ())
+    }
+    
+    /// Add a new opening token to the list, opening the innermost pending block
+    #[inline(always)]
+    pub fn add_left(&mut self, idx: usize) -> Result<(), BalanceBlockError> {
+        self.add_left_kind(idx, ())
+    }
+}
+
+impl<K> RevBlocks<K> {
+    /// Construct an empty `RevBlocks` structure
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self::with_limits(Limits::new())
+    }
+    
+    /** Construct an empty `RevBlocks` structure with resource limits.
+        Blocks are opened by their closing token, which is the one blamed for exceeding a limit */
+    #[inline(always)]
+    pub const fn with_limits(limits: Limits) -> Self {
+        Self {
+            inner: Vec::new(),
+            pending: Vec::new(),
+            limits
+        }
+    }
+    
+    /// Retrieve the resource limits
+    #[inline(always)]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+    
+    /// Add a new closing token of the given kind to the list, checking the limits
+    #[inline(always)]
+    pub fn add_right_kind(&mut self, idx: usize, kind: K) -> Result<(), BalanceBlockError<K>> {
+        self.add_right_token(idx..idx + 1, kind)
+    }
+    
+    /** Add a new closing token of the given kind spanning `range` to the list, checking the limits.
+        The token is not added if it is reversed or if a limit is exceeded */
+    pub fn add_right_token(&mut self, range: Range<usize>, kind: K) -> Result<(), BalanceBlockError<K>> {
+        if range.start > range.end {
+            return Err(BalanceBlockError::InvalidToken {start: range.start, end: range.end});
+        }
+        
+        let depth = self.pending.len() + 1;
+        let blocks = self.inner.len() + depth;
+        
+        // Every pending token ends up in a block
+        let memory = limits::memory::<Block<Balanced, K>, (Range<usize>, K)>(blocks, depth);
+        
+        self.limits.check(depth, blocks, memory, range.start)?;
+        self.limits.reserve((&mut self.inner, blocks), (&mut self.pending, depth), range.start)?;
+        self.pending.push((range, kind));
+        Ok(())
+    }
+    
+    /// Check whether the tokens are balanced
+    pub fn is_valid(&self) -> bool {
+        self.pending.is_empty()
+    }
+    
+    /// Iterate over the closing tokens still waiting for an opening token, from the outermost to the innermost
+    pub fn unopened(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.pending.iter().map(|(range, _)| range.start)
+    }
+    
+    /** Check the validity of the structure and return a vector of balanced blocks.
+        The blocks are ordered by their opening index */
+    pub fn consume(mut self) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>> {
+        // The innermost pending closing token is the leftmost one
+        if let Some((closing, _)) = self.pending.last() {
+            return Err(BalanceBlockError::ExtraRight {closing: closing.start});
+        }
+        
+        // Blocks are completed in decreasing order of their opening index
+        self.inner.reverse();
+        Ok(self.inner)
+    }
+}
+
+impl<K: PartialEq + Clone> RevBlocks<K> {
+    /** Add a new opening token of the given kind to the list,
+        opening the innermost pending block */
+    #[inline(always)]
+    pub fn add_left_kind(&mut self, idx: usize, kind: K) -> Result<(), BalanceBlockError<K>> {
+        self.add_left_token(idx..idx + 1, kind)
+    }
+    
+    /** Add a new opening token of the given kind spanning `range` to the list,
+        opening the innermost pending block.
+        The token must not be reversed nor end after the start of the closing token */
+    pub fn add_left_token(&mut self, range: Range<usize>, kind: K) -> Result<(), BalanceBlockError<K>> {
+        if range.start > range.end {
+            return Err(BalanceBlockError::InvalidToken {start: range.start, end: range.end});
+        }
+        
+        let Some((closing, expected)) = self.pending.last() else {
+            return Err(BalanceBlockError::ExtraLeft {unclosed: vec![range.start]});
+        };
+        
+        if range.end > closing.start {
+            return Err(BalanceBlockError::InvalidToken {start: range.start, end: range.end});
+        }
+        
+        if *expected != kind {
+            return Err(BalanceBlockError::Mismatch {
+                expected: expected.clone(),
+                found: kind,
+                opening: range.start,
+                closing: closing.start
+            });
+        }
+        
+        let closing = closing.clone();
+        self.pending.pop();
+        self.inner.push(Block {
+            opening: range.start,
+            closing: closing.start,
+            kind,
+            opening_len: range.len(),
+            closing_len: closing.len()
+        });
+        Ok(())
+    }
+}
+
+impl<K> Default for RevBlocks<K> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,274 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use crate::{BalanceBlockError, Balanced, Block, Blocks, Event};
+
+/** A set of delimiter pairs and lexical rules for `Blocks::scan`.
+    The kind of a block is the position of its pair in the set.
+    Delimiters may span several characters, the longest one matching wins.
+    Delimiters starting or ending with a word character, such as `begin`, only match whole words.
+    Delimiters inside string literals and comments are ignored. */
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Delimiters {
+    pairs: Vec<(String, String)>,
+    strings: Vec<(char, Option<char>)>,
+    line_comments: Vec<String>,
+    block_comments: Vec<BlockComment>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BlockComment {
+    open: String,
+    close: String,
+    nested: bool
+}
+
+impl Delimiters {
+    /// Construct an empty set
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            pairs: Vec::new(),
+            strings: Vec::new(),
+            line_comments: Vec::new(),
+            block_comments: Vec::new()
+        }
+    }
+    
+    /// Construct the set of `()`, `[]` and `{}`, in this order
+    pub fn brackets() -> Self {
+        Self::new()
+            .pair('(', ')')
+            .pair('[', ']')
+            .pair('{', '}')
+    }
+    
+    /** Add a pair of an opening and a closing delimiter, such as `'('` and `')'` or `"begin"` and `"end"`.
+        Panics if either delimiter is empty, if both are the same or if one is already in the set */
+    pub fn pair(mut self, open: impl Into<String>, close: impl Into<String>) -> Self {
+        let (open, close) = (open.into(), close.into());
+        assert!(!open.is_empty() && !close.is_empty(), "empty delimiter");
+        assert!(
+            open != close && !self.pairs.iter().flat_map(|(o, c)| [o, c]).any(|d| *d == open || *d == close),
+            "duplicate delimiter"
+        );
+        
+        self.pairs.push((open, close));
+        self
+    }
+    
+    /** Add a string literal delimited by `quote` on both ends.
+        Inside it, `escape` makes the following character literal */
+    pub fn string(mut self, quote: char, escape: Option<char>) -> Self {
+        self.strings.push((quote, escape));
+        self
+    }
+    
+    /** Add a comment running from `start` to the end of the line.
+        Panics if `start` is empty */
+    pub fn line_comment(mut self, start: &str) -> Self {
+        assert!(!start.is_empty(), "empty comment marker");
+        
+        self.line_comments.push(start.into());
+        self
+    }
+    
+    /** Add a comment running from `open` to the first `close`.
+        Panics if either marker is empty */
+    pub fn block_comment(mut self, open: &str, close: &str) -> Self {
+        assert!(!open.is_empty() && !close.is_empty(), "empty comment marker");
+        
+        self.block_comments.push(BlockComment {open: open.into(), close: close.into(), nested: false});
+        self
+    }
+    
+    /** Add a comment running from `open` to the matching `close`, like Rust's `/* /* */ */`.
+        Panics if either marker is empty */
+    pub fn nested_block_comment(mut self, open: &str, close: &str) -> Self {
+        assert!(!open.is_empty() && !close.is_empty(), "empty comment marker");
+        
+        self.block_comments.push(BlockComment {open: open.into(), close: close.into(), nested: true});
+        self
+    }
+    
+    /// Retrieve the pairs, indexed by kind
+    #[inline(always)]
+    pub fn pairs(&self) -> &[(String, String)] {
+        &self.pairs
+    }
+    
+    /** Match the longest delimiter at the start of a text, returning its length in bytes.
+        The start of the text counts as a word boundary */
+    #[inline(always)]
+    pub fn classify(&self, text: &str) -> Option<(Event<usize>, usize)> {
+        self.classify_after(text, false)
+    }
+    
+    /// Match the longest delimiter at the start of a text, which follows a word character if `after_word`
+    fn classify_after(&self, text: &str, after_word: bool) -> Option<(Event<usize>, usize)> {
+        self.pairs
+            .iter()
+            .enumerate()
+            .flat_map(|(kind, (open, close))| [(Event::Open(kind), open), (Event::Close(kind), close)])
+            .filter(|(_, delimiter)| text.starts_with(delimiter.as_str()))
+            .filter(|(_, delimiter)| {
+                let rest = &text[delimiter.len()..];
+                let starts_word = delimiter.chars().next().is_some_and(is_word);
+                let ends_word = delimiter.chars().next_back().is_some_and(is_word);
+                let before_word = rest.chars().next().is_some_and(is_word);
+                
+                // A word delimiter must not run into a neighbouring word
+                !(starts_word && after_word || ends_word && before_word)
+            })
+            .max_by_key(|(_, delimiter)| delimiter.len())
+            .map(|(event, delimiter)| (event, delimiter.len()))
+    }
+    
+    /// Iterate over the tokens of a text outside of string literals and comments, with their byte ranges
+    pub fn tokens<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (Range<usize>, Event<usize>)> + 'a {
+        Tokens {
+            delimiters: self,
+            text,
+            pos: 0
+        }
+    }
+}
+
+/// Check whether a character belongs to identifiers and keywords
+#[inline(always)]
+pub(crate) fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Lexer yielding the tokens of a text
+struct Tokens<'a> {
+    delimiters: &'a Delimiters,
+    text: &'a str,
+    pos: usize
+}
+
+impl Tokens<'_> {
+    /// Skip a region starting at the current position, if any
+    fn skip_region(&mut self) -> bool {
+        let rest = &self.text[self.pos..];
+        let delimiters = self.delimiters;
+        
+        if delimiters.line_comments.iter().any(|start| rest.starts_with(start.as_str())) {
+            self.pos += rest.find('\n').unwrap_or(rest.len());
+            return true;
+        }
+        
+        if let Some(comment) = delimiters.block_comments.iter().find(|comment| rest.starts_with(comment.open.as_str())) {
+            self.skip_block_comment(comment);
+            return true;
+        }
+        
+        let mut chars = rest.char_indices();
+        let Some((_, first)) = chars.next() else {
+            return false;
+        };
+        let Some(&(quote, escape)) = delimiters.strings.iter().find(|&&(quote, _)| quote == first) else {
+            return false;
+        };
+        
+        // An unterminated string literal runs to the end of the text
+        let mut end = rest.len();
+        
+        while let Some((n, c)) = chars.next() {
+            if Some(c) == escape {
+                chars.next();
+            } else if c == quote {
+                end = n + c.len_utf8();
+                break;
+            }
+        }
+        
+        self.pos += end;
+        true
+    }
+    
+    fn skip_block_comment(&mut self, comment: &BlockComment) {
+        let mut depth = 0usize;
+        
+        // An unterminated comment runs to the end of the text
+        while self.pos < self.text.len() {
+            let rest = &self.text[self.pos..];
+            
+            if (depth == 0 || comment.nested) && rest.starts_with(comment.open.as_str()) {
+                depth += 1;
+                self.pos += comment.open.len();
+            } else if rest.starts_with(comment.close.as_str()) {
+                depth -= 1;
+                self.pos += comment.close.len();
+                
+                if depth == 0 {
+                    return;
+                }
+            } else {
+                self.pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = (Range<usize>, Event<usize>);
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.text.len() {
+            if self.skip_region() {
+                continue;
+            }
+            
+            let pos = self.pos;
+            let rest = &self.text[pos..];
+            let after_word = self.text[..pos].chars().next_back().is_some_and(is_word);
+            
+            if let Some((event, len)) = self.delimiters.classify_after(rest, after_word) {
+                self.pos += len;
+                return Some((pos..self.pos, event));
+            }
+            
+            self.pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+        
+        None
+    }
+}
+
+impl Blocks<usize> {
+    /** Balance the delimiters of a text, skipping every other character.
+        Indices are byte offsets and kinds are positions in `delimiters` */
+    pub fn scan(text: &str, delimiters: &Delimiters) -> Result<Vec<Block<Balanced, usize>>, BalanceBlockError<usize>> {
+        Self::scan_tokens(delimiters.tokens(text))
+    }
+    
+    /** Balance the delimiters of a text, skipping every other character.
+        Indices are char offsets and kinds are positions in `delimiters`.
+        Reports take byte offsets, so the indices of an error must be converted before reporting it on non-ASCII text */
+    pub fn scan_chars(text: &str, delimiters: &Delimiters) -> Result<Vec<Block<Balanced, usize>>, BalanceBlockError<usize>> {
+        // Tokens come in order, so char offsets are counted from the previous token
+        let (mut bytes, mut chars) = (0, 0);
+        let mut to_chars = |n: usize| {
+            chars += text[bytes..n].chars().count();
+            bytes = n;
+            chars
+        };
+        let tokens = delimiters.tokens(text).map(|(range, event)| {
+            (to_chars(range.start)..to_chars(range.end), event)
+        });
+        
+        Self::scan_tokens(tokens)
+    }
+    
+    fn scan_tokens(tokens: impl Iterator<Item = (Range<usize>, Event<usize>)>) -> Result<Vec<Block<Balanced, usize>>, BalanceBlockError<usize>> {
+        let mut blocks = Self::new();
+        
+        for (range, event) in tokens {
+            blocks.add_token(range, event)?;
+        }
+        
+        blocks.consume()
+    }
+}
@@ -0,0 +1,168 @@
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::{Block, BlockState};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use crate::limits::{self, Limits};
+#[cfg(feature = "alloc")]
+use crate::{BalanceBlockError, Blocks, Unbalanced};
+
+impl<T: BlockState + Serialize, K: Serialize> Serialize for Block<T, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Block", 5)?;
+        
+        state.serialize_field("opening", &self.opening)?;
+        state.serialize_field("closing", &self.closing)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("opening_len", &self.opening_len)?;
+        state.serialize_field("closing_len", &self.closing_len)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Block")]
+struct RawBlock<T, K> {
+    opening: usize,
+    closing: T,
+    kind: K,
+    #[serde(default = "single")]
+    opening_len: usize,
+    #[serde(default = "single")]
+    closing_len: usize
+}
+
+/// Tokens span a single index by default
+const fn single() -> usize {
+    1
+}
+
+/// Deserialization checks that the closing token starts after the opening one ends and that both fit in `usize`
+impl<'de, T, K> Deserialize<'de> for Block<T, K>
+where
+    T: BlockState + Deserialize<'de>,
+    K: Deserialize<'de>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawBlock {opening, closing, kind, opening_len, closing_len} = RawBlock::<T, K>::deserialize(deserializer)?;
+        let opening_end = opening
+            .checked_add(opening_len)
+            .ok_or_else(|| D::Error::custom("opening token out of bounds"))?;
+        
+        if let Some(closing) = closing.closing_index() {
+            if closing < opening_end {
+                return Err(D::Error::custom("closing token before the end of the opening token"));
+            }
+            
+            if closing.checked_add(closing_len).is_none() {
+                return Err(D::Error::custom("closing token out of bounds"));
+            }
+        }
+        
+        Ok(Self {opening, closing, kind, opening_len, closing_len})
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Serialize> Serialize for Blocks<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Blocks", 4)?;
+        
+        state.serialize_field("inner", &self.inner)?;
+        state.serialize_field("open", &self.open)?;
+        state.serialize_field("limits", &self.limits)?;
+        state.serialize_field("error", &self.error)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Deserialize)]
+#[serde(rename = "Blocks", bound(deserialize = "K: Deserialize<'de>"))]
+struct RawBlocks<K> {
+    inner: Vec<Block<Unbalanced, K>>,
+    open: Vec<usize>,
+    #[serde(default)]
+    limits: Limits,
+    #[serde(default)]
+    error: Option<BalanceBlockError<K>>
+}
+
+/** Deserialization checks that the state is the one a left-to-right scan of the tokens
+    would reach: blocks ordered by their opening index, tokens not overlapping, properly nested,
+    and the blocks left open being exactly the open ones, within the limits */
+#[cfg(feature = "alloc")]
+impl<'de, K: Deserialize<'de>> Deserialize<'de> for Blocks<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawBlocks {inner, open, limits, error} = RawBlocks::deserialize(deserializer)?;
+        
+        if !is_reachable(&inner, &open) {
+            return Err(D::Error::custom("inconsistent blocks state"));
+        }
+        
+        let memory = limits::memory::<Block<Unbalanced, K>, usize>(inner.len(), open.len());
+        
+        if limits.check::<K>(open.len(), inner.len(), memory, 0).is_err() {
+            return Err(D::Error::custom("blocks state exceeds its limits"));
+        }
+        
+        Ok(Self {inner, open, limits, error})
+    }
+}
+
+/// Replay the tokens of the blocks in order and compare the outcome
+#[cfg(feature = "alloc")]
+fn is_reachable<K>(inner: &[Block<Unbalanced, K>], open: &[usize]) -> bool {
+    if !inner.windows(2).all(|pair| pair[0].opening < pair[1].opening) {
+        return false;
+    }
+    
+    // Every token ends before the next one starts
+    let mut tokens: Vec<_> = inner
+        .iter()
+        .flat_map(|block| {
+            let closing = block.closing.map(|closing| closing.get()..closing.get() + block.closing_len);
+            [Some(block.opening_token()), closing]
+        })
+        .flatten()
+        .collect();
+    tokens.sort_unstable_by_key(|token| (token.start, token.end));
+    
+    if !tokens.windows(2).all(|pair| pair[0].end <= pair[1].start) {
+        return false;
+    }
+    
+    let mut closings: Vec<_> = inner
+        .iter()
+        .enumerate()
+        .filter_map(|(i, block)| Some((block.closing?.get(), i)))
+        .collect();
+    closings.sort_unstable();
+    
+    let mut closings = closings.into_iter().peekable();
+    let mut stack = Vec::new();
+    
+    for (i, block) in inner.iter().enumerate() {
+        while let Some((_, j)) = closings.next_if(|&(closing, _)| closing < block.opening) {
+            if stack.pop() != Some(j) {
+                return false;
+            }
+        }
+        
+        if closings.peek().is_some_and(|&(closing, _)| closing == block.opening) {
+            return false;
+        }
+        
+        stack.push(i);
+    }
+    
+    for (_, j) in closings {
+        if stack.pop() != Some(j) {
+            return false;
+        }
+    }
+    
+    stack == open
+}
@@ -0,0 +1,101 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{Balanced, Block};
+
+/// Nesting statistics of balanced blocks
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct Stats<K = ()> {
+    /// Number of blocks
+    pub blocks: usize,
+    /** Depth of the most nested blocks, top-level blocks being at depth `0`, `None` without blocks.
+        One less than the number of open blocks that `Limits::max_depth` bounds */
+    pub max_depth: Option<usize>,
+    /// Number of blocks at every depth, top-level blocks being at depth `0`
+    pub depths: Vec<usize>,
+    /// Number of blocks of every kind
+    pub kinds: BTreeMap<K, usize>,
+    /// Mean length of the blocks, delimiters included, `0` without blocks
+    pub mean_len: f64,
+    /// Maximum length of the blocks, delimiters included
+    pub max_len: usize,
+    /// Longest top-level blocks, from the longest, ties being in input order
+    pub largest: Vec<Block<Balanced, K>>
+}
+
+impl<K: Ord + Clone> Stats<K> {
+    /** Compute the statistics of balanced blocks, keeping the `largest` longest top-level blocks.
+        The blocks must be ordered by their opening index, as returned by `Blocks::consume` */
+    pub fn new(blocks: &[Block<Balanced, K>], largest: usize) -> Self {
+        let mut depths = Vec::new();
+        let mut kinds = BTreeMap::new();
+        let mut total = 0;
+        let mut max_len = 0;
+        let mut top = Vec::new();
+        
+        // Ends of the enclosing blocks of the current one
+        let mut stack: Vec<usize> = Vec::new();
+        
+        for block in blocks {
+            let end = block.closing_token().end;
+            let len = block.len();
+            
+            while stack.last().is_some_and(|&enclosing| enclosing <= block.opening) {
+                stack.pop();
+            }
+            
+            if stack.len() == depths.len() {
+                depths.push(0);
+            }
+            
+            if stack.is_empty() {
+                top.push(block);
+            }
+            
+            depths[stack.len()] += 1;
+            *kinds.entry(block.kind.clone()).or_insert(0) += 1;
+            total += len;
+            max_len = max_len.max(len);
+            stack.push(end);
+        }
+        
+        top.sort_by_key(|block| core::cmp::Reverse(block.len()));
+        
+        Self {
+            blocks: blocks.len(),
+            max_depth: depths.len().checked_sub(1),
+            depths,
+            kinds,
+            mean_len: if blocks.is_empty() {0.0} else {total as f64 / blocks.len() as f64},
+            max_len,
+            largest: top.into_iter().take(largest).cloned().collect()
+        }
+    }
+}
+
+impl<K> Stats<K> {
+    /** Compute the number of blocks containing every position of `0..len`, delimiters included.
+        Positions in a top-level block count `1`, like the open blocks bounded by `Limits::max_depth`,
+        so that the maximum of the profile is `max_depth + 1`. The blocks may come in any order */
+    pub fn depth_profile(blocks: &[Block<Balanced, K>], len: usize) -> Vec<usize> {
+        // Changes of depth at every position
+        let mut delta = vec![0isize; len + 1];
+        
+        for block in blocks {
+            let start = block.opening.min(len);
+            let end = block.closing_token().end.min(len);
+            
+            delta[start] += 1;
+            delta[end] -= 1;
+        }
+        
+        delta[..len]
+            .iter()
+            .scan(0, |depth, &delta| {
+                *depth += delta;
+                Some(*depth as usize)
+            })
+            .collect()
+    }
+}
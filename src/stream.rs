@@ -0,0 +1,141 @@
+use core::fmt::{self, Debug, Display, Formatter};
+use std::io::{self, ErrorKind, Read};
+use std::vec;
+use std::vec::Vec;
+use crate::{BalanceBlockError, Balanced, Block, Blocks, Event, Limits};
+
+/// Size of the buffer used to read from a `Read` source
+const READ_CHUNK: usize = 64 * 1024;
+
+/** A streaming front end over `Blocks`.
+    Bytes are fed in successive chunks and classified into tokens by `F`;
+    blocks stay open across chunk boundaries and indices are global offsets. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct ChunkedBlocks<K, F> {
+    blocks: Blocks<K>,
+    offset: usize,
+    classify: F
+}
+
+impl<K, F> ChunkedBlocks<K, F>
+where
+    K: PartialEq + Clone,
+    F: FnMut(u8) -> Option<Event<K>>
+{
+    /// Construct an empty `ChunkedBlocks` structure with a byte classifier
+    #[inline(always)]
+    pub const fn new(classify: F) -> Self {
+        Self::with_limits(Limits::new(), classify)
+    }
+    
+    /// Construct an empty `ChunkedBlocks` structure with a byte classifier and resource limits
+    #[inline(always)]
+    pub const fn with_limits(limits: Limits, classify: F) -> Self {
+        Self {
+            blocks: Blocks::with_limits(limits),
+            offset: 0,
+            classify
+        }
+    }
+    
+    /** Process the next chunk of bytes.
+        The structure must not be fed any further after an error */
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), BalanceBlockError<K>> {
+        for (n, &byte) in chunk.iter().enumerate() {
+            if let Some(event) = (self.classify)(byte) {
+                self.blocks.add(self.offset + n, event)?;
+            }
+        }
+        
+        self.offset += chunk.len();
+        Ok(())
+    }
+    
+    /// Process the next chunk of text, indices being byte offsets
+    #[inline(always)]
+    pub fn feed_str(&mut self, chunk: &str) -> Result<(), BalanceBlockError<K>> {
+        self.feed(chunk.as_bytes())
+    }
+    
+    /// Process everything left in a reader
+    pub fn read_from<R: Read>(&mut self, mut reader: R) -> Result<(), ReadBlocksError<K>> {
+        let mut buf = vec![0; READ_CHUNK];
+        
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(len) => self.feed(&buf[..len])?,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into())
+            }
+        }
+    }
+    
+    /// Retrieve the global offset of the next chunk
+    #[inline(always)]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+    
+    /// Retrieve the underlying `Blocks` structure
+    #[inline(always)]
+    pub fn blocks(&self) -> &Blocks<K> {
+        &self.blocks
+    }
+    
+    /// Check the validity of the structure and return a vector of balanced blocks
+    #[inline(always)]
+    pub fn consume(self) -> Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>> {
+        self.blocks.consume()
+    }
+    
+    /// Balance a whole reader without loading it into memory
+    pub fn balance_reader<R: Read>(reader: R, classify: F) -> Result<Vec<Block<Balanced, K>>, ReadBlocksError<K>> {
+        let mut stream = Self::new(classify);
+        
+        stream.read_from(reader)?;
+        Ok(stream.consume()?)
+    }
+}
+
+/// Error of balancing a reader
+#[derive(Debug)]
+pub enum ReadBlocksError<K = ()> {
+    /// Reading failed
+    Io(io::Error),
+    /// The input is unbalanced
+    Balance(BalanceBlockError<K>),
+}
+
+impl<K: Debug> Display for ReadBlocksError<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Balance(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<K: Debug> core::error::Error for ReadBlocksError<K> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => err.source(),
+            Self::Balance(err) => err.source(),
+        }
+    }
+}
+
+impl<K> From<io::Error> for ReadBlocksError<K> {
+    #[inline(always)]
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl<K> From<BalanceBlockError<K>> for ReadBlocksError<K> {
+    #[inline(always)]
+    fn from(err: BalanceBlockError<K>) -> Self {
+        Self::Balance(err)
+    }
+}
@@ -0,0 +1,202 @@
+use alloc::{vec, vec::Vec};
+use crate::{BalanceBlockError, Balanced, Block, Blocks};
+
+/// Identifier of a node in a `BlockTree`, the position of its block in opening order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Retrieve the position of the block in opening order
+    #[inline(always)]
+    pub const fn index(self) -> usize {
+        self.0
+    }
+}
+
+/** A tree of balanced blocks.
+    Every block is a node; the children of a node are the blocks directly nested in it. */
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct BlockTree<K = ()> {
+    blocks: Vec<Block<Balanced, K>>,
+    parent: Vec<Option<NodeId>>,
+    next_sibling: Vec<Option<NodeId>>,
+    prev_sibling: Vec<Option<NodeId>>,
+    depth: Vec<usize>,
+    post_order: Vec<NodeId>
+}
+
+impl<K> BlockTree<K> {
+    /** Build a tree from balanced blocks.
+        The blocks must be ordered by their opening index, as returned by `Blocks::consume` */
+    pub fn new(blocks: Vec<Block<Balanced, K>>) -> Self {
+        let len = blocks.len();
+        let mut parent = vec![None; len];
+        let mut next_sibling = vec![None; len];
+        let mut prev_sibling = vec![None; len];
+        let mut depth = vec![0; len];
+        let mut post_order = Vec::with_capacity(len);
+        
+        // Open ancestors of the current block and the last child seen at every level
+        let mut stack: Vec<usize> = Vec::new();
+        let mut last_child: Vec<Option<usize>> = vec![None];
+        
+        for (i, block) in blocks.iter().enumerate() {
+            while let Some(&top) = stack.last() {
+                if blocks[top].closing > block.opening {
+                    break;
+                }
+                
+                post_order.push(NodeId(top));
+                stack.pop();
+                last_child.pop();
+            }
+            
+            if let Some(prev) = last_child[stack.len()] {
+                next_sibling[prev] = Some(NodeId(i));
+                prev_sibling[i] = Some(NodeId(prev));
+            }
+            
+            parent[i] = stack.last().copied().map(NodeId);
+            depth[i] = stack.len();
+            last_child[stack.len()] = Some(i);
+            stack.push(i);
+            last_child.push(None);
+        }
+        
+        post_order.extend(stack.into_iter().rev().map(NodeId));
+        
+        Self {
+            blocks,
+            parent,
+            next_sibling,
+            prev_sibling,
+            depth,
+            post_order
+        }
+    }
+    
+    /// Retrieve the number of blocks
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+    
+    /// Check whether the tree has no blocks
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+    
+    /// Retrieve the blocks, ordered by their opening index
+    #[inline(always)]
+    pub fn blocks(&self) -> &[Block<Balanced, K>] {
+        &self.blocks
+    }
+    
+    /// Retrieve the block of a node
+    #[inline(always)]
+    pub fn get(&self, id: NodeId) -> &Block<Balanced, K> {
+        &self.blocks[id.0]
+    }
+    
+    /// Retrieve the node of the block directly enclosing a node
+    #[inline(always)]
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parent[id.0]
+    }
+    
+    /// Retrieve the next node with the same parent
+    #[inline(always)]
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.next_sibling[id.0]
+    }
+    
+    /// Retrieve the previous node with the same parent
+    #[inline(always)]
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.prev_sibling[id.0]
+    }
+    
+    /// Retrieve the nesting depth of a node, `0` for the roots
+    #[inline(always)]
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.depth[id.0]
+    }
+    
+    /// Iterate over the children of a node in order
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        // The first child, if any, immediately follows its parent in opening order
+        let first = Some(NodeId(id.0 + 1)).filter(|&child| {
+            child.0 < self.len() && self.parent(child) == Some(id)
+        });
+        
+        core::iter::successors(first, |&child| self.next_sibling(child))
+    }
+    
+    /// Iterate over the top-level nodes in order
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        let first = (!self.is_empty()).then_some(NodeId(0));
+        
+        core::iter::successors(first, |&root| self.next_sibling(root))
+    }
+    
+    /// Iterate over the nodes in pre-order, parents before their children
+    pub fn pre_order(&self) -> impl DoubleEndedIterator<Item = NodeId> + ExactSizeIterator {
+        (0..self.len()).map(NodeId)
+    }
+    
+    /// Iterate over the nodes in post-order, children before their parents
+    pub fn post_order(&self) -> impl DoubleEndedIterator<Item = NodeId> + ExactSizeIterator + '_ {
+        self.post_order.iter().copied()
+    }
+    
+    /// Find the node whose opening token is at `idx` in logarithmic time
+    pub fn block_at_opening(&self, idx: usize) -> Option<NodeId> {
+        self.blocks
+            .binary_search_by_key(&idx, Block::opening)
+            .ok()
+            .map(NodeId)
+    }
+    
+    /// Find the node whose closing token is at `idx` in logarithmic time
+    pub fn block_at_closing(&self, idx: usize) -> Option<NodeId> {
+        self.post_order
+            .binary_search_by_key(&idx, |&id| self.get(id).closing)
+            .ok()
+            .map(|i| self.post_order[i])
+    }
+    
+    /// Find the innermost node containing `idx`, delimiters included, in logarithmic time
+    pub fn innermost_containing(&self, idx: usize) -> Option<NodeId> {
+        let last = self.blocks.partition_point(|block| block.opening <= idx).checked_sub(1)?;
+        
+        if self.blocks[last].closing_token().end > idx {
+            return Some(NodeId(last));
+        }
+        
+        // No block opens between the last closing token before `idx` and `idx` itself,
+        // so the parent of the block closed there is the innermost one still open
+        let closed = self.post_order.partition_point(|&id| self.get(id).closing_token().end <= idx) - 1;
+        self.parent(self.post_order[closed])
+    }
+    
+    /// Iterate over the nodes containing `idx`, from the innermost to the outermost
+    pub fn enclosing_chain(&self, idx: usize) -> impl Iterator<Item = NodeId> + '_ {
+        core::iter::successors(self.innermost_containing(idx), |&id| self.parent(id))
+    }
+    
+    /// Retrieve the blocks, ordered by their opening index
+    #[inline(always)]
+    pub fn into_blocks(self) -> Vec<Block<Balanced, K>> {
+        self.blocks
+    }
+}
+
+impl<K> Blocks<K> {
+    /// Check the validity of the structure and return a tree of balanced blocks
+    #[inline(always)]
+    pub fn into_tree(self) -> Result<BlockTree<K>, BalanceBlockError<K>> {
+        self.consume().map(BlockTree::new)
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::vec::Vec;
+use crate::{Balanced, Block, BlockTree};
+
+/// What a traversal does after a callback of a `BlockVisitor`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Visit {
+    /// Carry on
+    #[default]
+    Continue,
+    /** Skip the blocks nested in the block just entered, its `leave` callback still being called.
+        Same as `Continue` when returned by `leave` */
+    SkipChildren,
+    /// End the traversal at once, without calling any further callback
+    Stop
+}
+
+/** Callbacks of a depth-first traversal of balanced blocks, top-level blocks being at depth `0`.
+    The traversal keeps its own stack, so that deep nesting cannot overflow the call stack. */
+pub trait BlockVisitor<K = ()> {
+    /// Called before the blocks nested in `block`
+    #[inline(always)]
+    fn enter(&mut self, _block: &Block<Balanced, K>, _depth: usize) -> Visit {
+        Visit::Continue
+    }
+    
+    /// Called after the blocks nested in `block`
+    #[inline(always)]
+    fn leave(&mut self, _block: &Block<Balanced, K>, _depth: usize) -> Visit {
+        Visit::Continue
+    }
+    
+    /** Traverse blocks ordered by their opening index, as returned by `Blocks::consume`.
+        Returns `false` if a callback stopped the traversal */
+    fn walk(&mut self, blocks: &[Block<Balanced, K>]) -> bool where Self: Sized {
+        // Indices of the blocks entered but not left yet
+        let mut stack: Vec<usize> = Vec::new();
+        let mut next = 0;
+        
+        while let Some(block) = blocks.get(next) {
+            let current = next;
+            
+            while let Some(&top) = stack.last() {
+                if blocks[top].closing_token().end > block.opening {
+                    break;
+                }
+                
+                stack.pop();
+                if self.leave(&blocks[top], stack.len()) == Visit::Stop {
+                    return false;
+                }
+            }
+            
+            next += 1;
+            
+            match self.enter(block, stack.len()) {
+                Visit::Continue => {}
+                // Nested blocks directly follow the block
+                Visit::SkipChildren => next += blocks[next..].partition_point(|nested| nested.opening < block.closing),
+                Visit::Stop => return false
+            }
+            
+            stack.push(current);
+        }
+        
+        while let Some(top) = stack.pop() {
+            if self.leave(&blocks[top], stack.len()) == Visit::Stop {
+                return false;
+            }
+        }
+        
+        true
+    }
+    
+    /** Traverse the blocks of a tree.
+        Returns `false` if a callback stopped the traversal */
+    #[inline(always)]
+    fn walk_tree(&mut self, tree: &BlockTree<K>) -> bool where Self: Sized {
+        self.walk(tree.blocks())
+    }
+}
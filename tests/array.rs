@@ -0,0 +1,89 @@
+//! Heap-free balancing, which must work without any feature
+
+use blocks::{ArrayBlocks, BalanceBlockError, ByteDelimiters, Event};
+
+fn balance<const N: usize>(code: &[u8]) -> (ArrayBlocks<N, usize>, Result<(), BalanceBlockError<usize>>) {
+    let mut blocks = ArrayBlocks::new();
+    let result = ByteDelimiters::brackets().try_find(code, |n, event| match event {
+        Event::Open(kind) => blocks.add_left_kind(n, kind),
+        Event::Close(kind) => blocks.add_right_kind(n, kind)
+    });
+    
+    (blocks, result)
+}
+
+#[test]
+fn balances_without_allocating() {
+    //                               0123456789
+    let (blocks, result) = balance::<4>(b"f(a[0]{})");
+    
+    result.unwrap();
+    
+    let blocks = blocks.blocks().unwrap();
+    let mut triples = [(0, 0, 0); 3];
+    
+    for (triple, block) in triples.iter_mut().zip(blocks) {
+        *triple = (block.opening(), block.closing(), *block.kind());
+    }
+    
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(triples, [(1, 8, 0), (3, 5, 1), (6, 7, 2)]);
+}
+
+#[test]
+fn mismatch_and_extra_right() {
+    let (_, result) = balance::<4>(b"(]");
+    assert!(matches!(result, Err(BalanceBlockError::Mismatch {expected: 0, found: 1, opening: 0, closing: 1})));
+    
+    let (_, result) = balance::<4>(b"()]");
+    assert!(matches!(result, Err(BalanceBlockError::ExtraRight {closing: 2})));
+}
+
+#[test]
+fn extra_left() {
+    let (blocks, result) = balance::<4>(b"([]{");
+    
+    result.unwrap();
+    assert!(!blocks.is_valid());
+    assert!(blocks.unclosed().eq([0, 3]));
+    
+    match blocks.blocks() {
+        #[cfg(feature = "alloc")]
+        Err(BalanceBlockError::ExtraLeft {unclosed, ..}) => assert_eq!(unclosed, [0, 3]),
+        #[cfg(not(feature = "alloc"))]
+        Err(BalanceBlockError::ExtraLeft {..}) => {}
+        other => panic!("unexpected {other:?}")
+    }
+}
+
+#[test]
+fn capacity_exceeded() {
+    let (blocks, result) = balance::<2>(b"()[]{}");
+    
+    assert!(matches!(result, Err(BalanceBlockError::CapacityExceeded {capacity: 2})));
+    assert_eq!(blocks.len(), 2);
+    assert!(blocks.is_valid());
+}
+
+#[test]
+fn closing_token_before_the_opening_one() {
+    let mut blocks = ArrayBlocks::<4>::new();
+    
+    blocks.add_left(3).unwrap();
+    assert!(matches!(blocks.add_right(3), Err(BalanceBlockError::InvalidToken {start: 3, end: 4})));
+    assert!(matches!(blocks.add_right(1), Err(BalanceBlockError::InvalidToken {start: 1, end: 2})));
+    assert!(blocks.add_right(4).is_ok());
+}
+
+#[test]
+fn debug_shows_open_blocks_as_unclosed() {
+    let (blocks, _) = balance::<4>(b"([]");
+    
+    assert_eq!(
+        format!("{blocks:?}"),
+        "ArrayBlocks { inner: [\
+            Block { opening: 0, closing: None, kind: 0, opening_len: 1, closing_len: 0 }, \
+            Block { opening: 1, closing: 2, kind: 1, opening_len: 1, closing_len: 1 }\
+        ] }"
+    );
+}
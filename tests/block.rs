@@ -0,0 +1,127 @@
+#![cfg(feature = "alloc")]
+
+use std::collections::HashSet;
+use blocks::{BalanceBlockError, Balanced, Block, Blocks, Delimiters, Recovering, RevBlocks, StrayPolicy};
+
+fn blocks(code: &str) -> Vec<Block<Balanced, usize>> {
+    Blocks::scan(code, &Delimiters::new().pair("begin", "end").pair('(', ')')).unwrap()
+}
+
+#[test]
+fn ranges_and_slices() {
+    //          0123456789012345678
+    let code = "begin f(x) end (y)";
+    let blocks = blocks(code);
+    
+    assert_eq!(blocks[0].outer_range(), 0..14);
+    assert_eq!(blocks[0].inner_range(), 5..11);
+    assert_eq!(blocks[0].slice_outer(code), "begin f(x) end");
+    assert_eq!(blocks[0].slice_inner(code), " f(x) ");
+    assert_eq!(blocks[1].slice_inner(code.as_bytes()), b"x");
+    assert_eq!(blocks[2].slice_inner(&['a'; 20][..]), ['a']);
+    assert_eq!(blocks[0].len(), 14);
+    assert_eq!(blocks[1].len(), 3);
+    assert!(!blocks[1].is_empty());
+}
+
+#[test]
+fn containment() {
+    let blocks = blocks("begin f(x) end (y)");
+    
+    assert!(blocks[0].contains(0) && blocks[0].contains(13));
+    assert!(!blocks[0].contains(14));
+    assert!(blocks[0].contains_block(&blocks[1]));
+    assert!(blocks[0].contains_block(&blocks[0]));
+    assert!(!blocks[0].contains_block(&blocks[2]));
+    assert!(blocks[0].is_ancestor_of(&blocks[1]));
+    assert!(!blocks[0].is_ancestor_of(&blocks[0]));
+    assert!(!blocks[1].is_ancestor_of(&blocks[0]));
+    
+    // Kinds need not match
+    let untyped = unsafe {Block::with_tokens_unchecked(0..1, 20..21, ())};
+    assert!(untyped.is_ancestor_of(&blocks[2]));
+}
+
+#[test]
+fn ordering_and_hashing() {
+    let blocks = blocks("begin f(x) end (y)");
+    let mut sorted = blocks.clone();
+    
+    sorted.reverse();
+    sorted.sort();
+    assert_eq!(sorted, blocks);
+    assert!(blocks[0] < blocks[1]);
+    
+    let unique: HashSet<_> = blocks.iter().chain(&blocks).collect();
+    assert_eq!(unique.len(), 3);
+}
+
+#[test]
+fn equality_ignores_missing_closing_width() {
+    use blocks::Unbalanced;
+    
+    let open: Block<Unbalanced> = Block::open(3);
+    let unchecked: Block<Unbalanced> = unsafe {Block::new_unchecked(3, None)};
+    
+    assert_eq!(open, unchecked);
+    assert_eq!(HashSet::from([open.clone(), unchecked]).len(), 1);
+    assert!(open < Block::open(4));
+    assert_ne!(open, Block::open_token(3..5, ()));
+}
+
+#[test]
+#[allow(clippy::reversed_empty_ranges)]
+fn rejects_reversed_tokens() {
+    let mut blocks = Blocks::new();
+    
+    assert!(matches!(blocks.add_left_token(5..3, ()), Err(BalanceBlockError::InvalidToken {start: 5, end: 3})));
+    blocks.add_left_token(0..5, ()).unwrap();
+    assert!(matches!(blocks.add_right_token(9..7, ()), Err(BalanceBlockError::InvalidToken {start: 9, end: 7})));
+    blocks.add_right_token(7..9, ()).unwrap();
+    assert_eq!(blocks.consume().unwrap()[0].inner_range(), 5..7);
+}
+
+#[test]
+fn rejects_closing_tokens_before_the_opening_end() {
+    let mut blocks = Blocks::new();
+    
+    blocks.add_left_token(0..5, ()).unwrap();
+    assert!(matches!(blocks.add_right_token(3..4, ()), Err(BalanceBlockError::InvalidToken {start: 3, end: 4})));
+    
+    let mut blocks = Blocks::new();
+    
+    blocks.add_left(10).unwrap();
+    assert!(matches!(blocks.add_right(2), Err(BalanceBlockError::InvalidToken {start: 2, end: 3})));
+    blocks.add_right(11).unwrap();
+    assert_eq!(blocks.consume().unwrap()[0].len(), 2);
+    
+    let mut recovering = Recovering::new(StrayPolicy::Error);
+    
+    recovering.add_left_token(0..5, ());
+    recovering.add_right_token(3..4, ());
+    recovering.add_right_token(6..7, ());
+    
+    let recovered = recovering.finish();
+    
+    assert_eq!(recovered.blocks[0].outer_range(), 0..7);
+    assert!(matches!(recovered.errors[..], [BalanceBlockError::InvalidToken {start: 3, end: 4}]));
+}
+
+#[test]
+fn right_to_left_tokens() {
+    //          0123456789012
+    let code = "begin (x) end";
+    let mut rev = RevBlocks::new();
+    
+    rev.add_right_token(10..13, 0).unwrap();
+    rev.add_right_token(8..9, 1).unwrap();
+    assert!(matches!(rev.add_left_token(6..9, 1), Err(BalanceBlockError::InvalidToken {start: 6, end: 9})));
+    rev.add_left_token(6..7, 1).unwrap();
+    rev.add_left_token(0..5, 0).unwrap();
+    
+    let rev = rev.consume().unwrap();
+    
+    assert_eq!(rev, blocks(code));
+    assert_eq!(rev[0].slice_outer(code), code);
+    assert_eq!(rev[0].slice_inner(code), " (x) ");
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use blocks::{ByteDelimiters, Event, Lanes};
+use common::Rng;
+
+fn found(delimiters: &ByteDelimiters, bytes: &[u8], lanes: Lanes) -> Vec<(usize, Event<usize>)> {
+    let mut found = Vec::new();
+    delimiters.find_with(bytes, lanes, |idx, event| found.push((idx, event)));
+    found
+}
+
+#[test]
+fn lanes_agree_with_scalar() {
+    let mut rng = Rng(0x2545f4914f6cdd1d);
+    let delimiters = ByteDelimiters::brackets().pair(b'<', b'>');
+    
+    for len in 0..300 {
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| match rng.next() % 4 {
+                0 => b"()[]{}<>"[rng.next() as usize % 8],
+                _ => rng.next() as u8
+            })
+            .collect();
+        let expected = found(&delimiters, &bytes, Lanes::Scalar);
+        
+        for lanes in [Lanes::Auto, Lanes::Sse2, Lanes::Avx2] {
+            assert_eq!(found(&delimiters, &bytes, lanes), expected, "{lanes:?} on {len} bytes");
+        }
+    }
+}
+
+#[test]
+fn classifies_kinds() {
+    let delimiters = ByteDelimiters::brackets();
+    
+    assert_eq!(delimiters.classify(b'('), Some(Event::Open(0)));
+    assert_eq!(delimiters.classify(b'}'), Some(Event::Close(2)));
+    assert_eq!(delimiters.classify(b'x'), None);
+}
+
+#[test]
+fn many_pairs_fall_back_to_scalar() {
+    let delimiters = (0..20).fold(ByteDelimiters::new(), |set, i| set.pair(b'a' + i, b'A' + i));
+    let bytes = b"abc CBA t T xyz";
+    
+    assert_eq!(found(&delimiters, bytes, Lanes::Auto), found(&delimiters, bytes, Lanes::Scalar));
+    assert_eq!(found(&delimiters, bytes, Lanes::Auto).len(), 8);
+}
+
+#[test]
+fn try_find_stops_at_the_first_failure() {
+    let delimiters = ByteDelimiters::brackets();
+    let bytes = [b"([x]".repeat(40), b"}".to_vec(), b"()".repeat(40)].concat();
+    
+    for lanes in [Lanes::Scalar, Lanes::Sse2, Lanes::Avx2] {
+        let mut found = 0;
+        let result = delimiters.try_find_with(&bytes, lanes, |idx, event| {
+            found += 1;
+            
+            match event {
+                Event::Close(2) => Err(idx),
+                _ => Ok(())
+            }
+        });
+        
+        assert_eq!(result, Err(160), "{lanes:?}");
+        assert_eq!(found, 121, "{lanes:?}");
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn scan_bytes_agrees_with_scan() {
+    use blocks::{Blocks, Delimiters};
+    
+    let text = r#"{"a": [1, 2, {"b": [[], {}]}], "c": {"d": [3]}}"#.repeat(10);
+    let json = format!("[{}]", text.replace("}{", "},{"));
+    
+    let bytes = Blocks::scan_bytes(json.as_bytes(), &ByteDelimiters::brackets()).unwrap();
+    let chars = Blocks::scan(&json, &Delimiters::brackets()).unwrap();
+    
+    let pairs = |blocks: &[blocks::Block<blocks::Balanced, usize>]| -> Vec<_> {
+        blocks.iter().map(|block| (block.opening(), block.closing(), *block.kind())).collect()
+    };
+    assert_eq!(pairs(&bytes), pairs(&chars));
+    assert!(Blocks::scan_bytes(b"[(])", &ByteDelimiters::brackets()).is_err());
+}
@@ -0,0 +1,37 @@
+//! Helpers shared by the integration tests, each using a few of them
+
+#![allow(dead_code)]
+
+use blocks::Event;
+
+/// A small xorshift generator, good enough for test buffers
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    
+    /// Draw a number below `n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Classify parentheses and square brackets, a block being of the kind of its opening char
+pub fn classify(c: char) -> Option<Event<char>> {
+    match c {
+        '(' | '[' => Some(Event::Open(c)),
+        ')' => Some(Event::Close('(')),
+        ']' => Some(Event::Close('[')),
+        _ => None
+    }
+}
+
+/// Classify bytes like `classify`
+pub fn classify_byte(byte: u8) -> Option<Event<char>> {
+    classify(byte as char)
+}
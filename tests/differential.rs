@@ -0,0 +1,419 @@
+//! Differential tests of `Blocks` against a naive reference matcher
+
+#![cfg(feature = "std")]
+
+mod common;
+
+use blocks::{
+    ArrayBlocks, BalanceBlockError, Balanced, Block, Blocks, ChunkedBlocks, Event, ReadBlocksError, Recovering,
+    RevBlocks, StrayPolicy
+};
+use common::classify_byte as classify;
+
+const MAX_LEN: usize = 14;
+
+#[derive(Debug, PartialEq)]
+enum Expected {
+    Balanced(Vec<(usize, usize)>),
+    ExtraRight(usize),
+    ExtraLeft(Vec<usize>)
+}
+
+/// Pair every opener with the first closer that brings the depth back to its level
+fn reference(code: &[u8]) -> Expected {
+    let mut depth = 0usize;
+    
+    for (n, &c) in code.iter().enumerate() {
+        match c {
+            b'[' => depth += 1,
+            _ if depth == 0 => return Expected::ExtraRight(n),
+            _ => depth -= 1
+        }
+    }
+    
+    let mut pairs = Vec::new();
+    let mut unclosed = Vec::new();
+    
+    for (opening, _) in code.iter().enumerate().filter(|(_, &c)| c == b'[') {
+        let mut depth = 0usize;
+        
+        for (closing, &c) in code.iter().enumerate().skip(opening) {
+            match c {
+                b'[' => depth += 1,
+                _ => depth -= 1
+            }
+            
+            if depth == 0 {
+                pairs.push((opening, closing));
+                break;
+            }
+        }
+        
+        if depth != 0 {
+            unclosed.push(opening);
+        }
+    }
+    
+    match unclosed.is_empty() {
+        true => Expected::Balanced(pairs),
+        false => Expected::ExtraLeft(unclosed)
+    }
+}
+
+fn subject(code: &[u8]) -> Expected {
+    let mut blocks = Blocks::new();
+    
+    for (n, &c) in code.iter().enumerate() {
+        match c {
+            b'[' => blocks.add_left(n).unwrap(),
+            _ => match blocks.add_right(n) {
+                Ok(()) => {}
+                Err(BalanceBlockError::ExtraRight { closing }) => return Expected::ExtraRight(closing),
+                Err(err) => panic!("unexpected error {err:?} in add_right")
+            }
+        }
+    }
+    
+    match blocks.consume() {
+        Ok(blocks) => Expected::Balanced(
+            blocks
+                .iter()
+                .map(|block| (block.opening(), block.closing()))
+                .collect()
+        ),
+        Err(BalanceBlockError::ExtraLeft { unclosed, .. }) => Expected::ExtraLeft(unclosed),
+        Err(err) => panic!("unexpected error {err:?} in consume")
+    }
+}
+
+/// Every string over `[` and `]` of length up to `MAX_LEN`
+fn all_strings() -> impl Iterator<Item = Vec<u8>> {
+    (0..=MAX_LEN).flat_map(|len| {
+        (0u32..1 << len).map(move |bits| {
+            (0..len)
+                .map(|i| if bits >> i & 1 == 0 { b'[' } else { b']' })
+                .collect()
+        })
+    })
+}
+
+#[test]
+fn matches_reference() {
+    for code in all_strings() {
+        assert_eq!(
+            subject(&code),
+            reference(&code),
+            "input {:?}",
+            String::from_utf8_lossy(&code)
+        );
+    }
+}
+
+#[test]
+fn siblings_and_nesting() {
+    assert_eq!(subject(b"[][]"), Expected::Balanced(vec![(0, 1), (2, 3)]));
+    assert_eq!(subject(b"[[]][]"), Expected::Balanced(vec![(0, 3), (1, 2), (4, 5)]));
+}
+
+#[test]
+fn unclosed_blocks() {
+    let mut blocks = Blocks::new();
+    
+    for (n, c) in "[[][".chars().enumerate() {
+        match c {
+            '[' => blocks.add_left(n).unwrap(),
+            _ => blocks.add_right(n).unwrap()
+        }
+    }
+    
+    let unclosed: Vec<_> = blocks.unclosed().map(|block| block.opening()).collect();
+    assert_eq!(unclosed, [0, 3]);
+    assert_eq!(subject(b"[[]["), Expected::ExtraLeft(vec![0, 3]));
+}
+
+#[test]
+fn leading_closer_is_an_error() {
+    let mut blocks = Blocks::new();
+    
+    assert!(matches!(blocks.add_right(0), Err(BalanceBlockError::ExtraRight { closing: 0 })));
+    assert!(matches!(blocks.add_right(1), Err(BalanceBlockError::ExtraRight { closing: 1 })));
+}
+
+const MAX_TYPED_LEN: usize = 8;
+
+#[derive(Debug, PartialEq)]
+enum ExpectedTyped {
+    Balanced(Vec<(usize, usize, char)>),
+    ExtraRight(usize),
+    ExtraLeft(Vec<usize>),
+    Mismatch(char, char, usize, usize)
+}
+
+fn opener(c: u8) -> Option<char> {
+    match c {
+        b'(' => Some('('),
+        b'[' => Some('['),
+        _ => None
+    }
+}
+
+fn closer(c: u8) -> char {
+    match c {
+        b')' => '(',
+        _ => '['
+    }
+}
+
+/// Pair every closer with the last opener that brings the depth back to its level
+fn reference_typed(code: &[u8]) -> ExpectedTyped {
+    let mut pairs = Vec::new();
+    
+    for (closing, &c) in code.iter().enumerate() {
+        if opener(c).is_some() {
+            continue;
+        }
+        
+        let mut depth = 0isize;
+        let partner = (0..=closing).rev().find(|&i| {
+            depth += if opener(code[i]).is_some() { 1 } else { -1 };
+            depth == 0
+        });
+        
+        let Some(opening) = partner else {
+            return ExpectedTyped::ExtraRight(closing);
+        };
+        
+        let (expected, found) = (opener(code[opening]).unwrap(), closer(c));
+        
+        if expected != found {
+            return ExpectedTyped::Mismatch(expected, found, opening, closing);
+        }
+        
+        pairs.push((opening, closing, expected));
+    }
+    
+    let unclosed: Vec<_> = (0..code.len())
+        .filter(|&i| opener(code[i]).is_some() && pairs.iter().all(|p| p.0 != i))
+        .collect();
+    
+    if !unclosed.is_empty() {
+        return ExpectedTyped::ExtraLeft(unclosed);
+    }
+    
+    pairs.sort();
+    ExpectedTyped::Balanced(pairs)
+}
+
+fn subject_typed(code: &[u8]) -> ExpectedTyped {
+    let mut blocks = Blocks::new();
+    
+    for (n, &c) in code.iter().enumerate() {
+        let result = match opener(c) {
+            Some(kind) => blocks.add_left_kind(n, kind),
+            None => blocks.add_right_kind(n, closer(c))
+        };
+        
+        match result {
+            Ok(()) => {}
+            Err(BalanceBlockError::ExtraRight { closing }) => return ExpectedTyped::ExtraRight(closing),
+            Err(BalanceBlockError::Mismatch { expected, found, opening, closing }) => {
+                return ExpectedTyped::Mismatch(expected, found, opening, closing)
+            }
+            Err(err) => panic!("unexpected error {err:?} in add_right_kind")
+        }
+    }
+    
+    match blocks.consume() {
+        Ok(blocks) => ExpectedTyped::Balanced(
+            blocks
+                .iter()
+                .map(|block| (block.opening(), block.closing(), *block.kind()))
+                .collect()
+        ),
+        Err(BalanceBlockError::ExtraLeft { unclosed, .. }) => ExpectedTyped::ExtraLeft(unclosed),
+        Err(err) => panic!("unexpected error {err:?} in consume")
+    }
+}
+
+/// Every string over `()[]` of length up to `MAX_TYPED_LEN`
+fn all_typed_strings() -> impl Iterator<Item = Vec<u8>> {
+    (0..=MAX_TYPED_LEN).flat_map(|len| {
+        (0u32..1 << (2 * len)).map(move |bits| {
+            (0..len)
+                .map(|i| b"()[]"[(bits >> (2 * i) & 3) as usize])
+                .collect()
+        })
+    })
+}
+
+#[test]
+fn typed_matches_reference() {
+    for code in all_typed_strings() {
+        assert_eq!(
+            subject_typed(&code),
+            reference_typed(&code),
+            "input {:?}",
+            String::from_utf8_lossy(&code)
+        );
+    }
+}
+
+#[test]
+fn recovering_agrees_on_first_error() {
+    for code in all_typed_strings() {
+        let mut recovering = Recovering::new(StrayPolicy::Error);
+        
+        for (n, &c) in code.iter().enumerate() {
+            match opener(c) {
+                Some(kind) => recovering.add_left_kind(n, kind),
+                None => recovering.add_right_kind(n, closer(c))
+            }
+        }
+        
+        let recovered = recovering.finish();
+        let first = match recovered.errors.first() {
+            None => outcome(Ok(recovered.blocks)),
+            Some(err) => outcome(Err(err.clone()))
+        };
+        
+        assert_eq!(first, reference_typed(&code), "input {:?}", String::from_utf8_lossy(&code));
+    }
+}
+
+#[test]
+fn right_to_left_agrees() {
+    for code in all_typed_strings() {
+        let mut rev = RevBlocks::new();
+        let mut ok = true;
+        
+        for (n, &c) in code.iter().enumerate().rev() {
+            match opener(c) {
+                Some(kind) => ok &= rev.add_left_kind(n, kind).is_ok(),
+                None => rev.add_right_kind(n, closer(c)).unwrap()
+            }
+            
+            if !ok {
+                break;
+            }
+        }
+        
+        let rev = ok.then(|| rev.consume().ok()).flatten().map(|blocks| {
+            blocks
+                .iter()
+                .map(|block| (block.opening(), block.closing(), *block.kind()))
+                .collect()
+        });
+        let expected = match reference_typed(&code) {
+            ExpectedTyped::Balanced(pairs) => Some(pairs),
+            _ => None
+        };
+        
+        assert_eq!(rev, expected, "input {:?}", String::from_utf8_lossy(&code));
+    }
+}
+
+#[test]
+fn right_to_left_leftmost_stray_closer() {
+    let mut rev = RevBlocks::new();
+    
+    for (n, c) in "]][]".char_indices().rev() {
+        match c {
+            '[' => rev.add_left(n).unwrap(),
+            _ => rev.add_right(n).unwrap()
+        }
+    }
+    
+    assert!(matches!(rev.consume(), Err(BalanceBlockError::ExtraRight { closing: 0 })));
+}
+
+fn outcome(result: Result<Vec<Block<Balanced, char>>, BalanceBlockError<char>>) -> ExpectedTyped {
+    match result {
+        Ok(blocks) => ExpectedTyped::Balanced(
+            blocks
+                .iter()
+                .map(|block| (block.opening(), block.closing(), *block.kind()))
+                .collect()
+        ),
+        Err(BalanceBlockError::ExtraRight { closing }) => ExpectedTyped::ExtraRight(closing),
+        Err(BalanceBlockError::ExtraLeft { unclosed, .. }) => ExpectedTyped::ExtraLeft(unclosed),
+        Err(BalanceBlockError::Mismatch { expected, found, opening, closing }) => {
+            ExpectedTyped::Mismatch(expected, found, opening, closing)
+        }
+        Err(err) => panic!("unexpected error {err:?}")
+    }
+}
+
+#[test]
+fn chunked_agrees() {
+    for code in all_typed_strings() {
+        let expected = reference_typed(&code);
+        
+        for split in 0..=code.len() {
+            let mut stream = ChunkedBlocks::new(classify);
+            let result = stream
+                .feed(&code[..split])
+                .and_then(|()| stream.feed(&code[split..]))
+                .and_then(|()| stream.consume());
+            
+            assert_eq!(outcome(result), expected, "input {:?} split at {split}", String::from_utf8_lossy(&code));
+        }
+    }
+}
+
+/// A reader handing out a single byte per call
+struct Trickle<'a>(&'a [u8]);
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some((&first, rest)) = self.0.split_first() else {
+            return Ok(0);
+        };
+        
+        buf[0] = first;
+        self.0 = rest;
+        Ok(1)
+    }
+}
+
+#[test]
+fn reader_agrees() {
+    for code in all_typed_strings().step_by(7) {
+        let result = match ChunkedBlocks::balance_reader(Trickle(&code), classify) {
+            Ok(blocks) => Ok(blocks),
+            Err(ReadBlocksError::Balance(err)) => Err(err),
+            Err(ReadBlocksError::Io(err)) => panic!("unexpected error {err}")
+        };
+        
+        assert_eq!(outcome(result), reference_typed(&code), "input {:?}", String::from_utf8_lossy(&code));
+    }
+}
+
+#[test]
+fn fixed_capacity_agrees() {
+    for code in all_typed_strings() {
+        let mut blocks = ArrayBlocks::<MAX_TYPED_LEN, char>::new();
+        let result = code
+            .iter()
+            .enumerate()
+            .try_for_each(|(n, &c)| match classify(c) {
+                Some(Event::Open(kind)) => blocks.add_left_kind(n, kind),
+                Some(Event::Close(kind)) => blocks.add_right_kind(n, kind),
+                None => Ok(())
+            })
+            .and_then(|()| blocks.blocks().map(<[_]>::to_vec));
+        
+        assert_eq!(outcome(result), reference_typed(&code), "input {:?}", String::from_utf8_lossy(&code));
+    }
+}
+
+#[test]
+fn fixed_capacity_exceeded() {
+    let mut blocks = ArrayBlocks::<2>::new();
+    
+    blocks.add_left(0).unwrap();
+    blocks.add_right(1).unwrap();
+    blocks.add_left(2).unwrap();
+    
+    assert!(matches!(blocks.add_left(3), Err(BalanceBlockError::CapacityExceeded { capacity: 2 })));
+    assert_eq!(blocks.unclosed().collect::<Vec<_>>(), [2]);
+}
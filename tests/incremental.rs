@@ -0,0 +1,84 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use blocks::{Balanced, Block, Incremental};
+use common::{classify, Rng};
+
+fn pairs(blocks: &[Block<Balanced, char>]) -> Vec<(usize, usize)> {
+    blocks.iter().map(|block| (block.opening(), block.closing())).collect()
+}
+
+#[test]
+fn reports_changes() {
+    //                                  0123456789
+    let mut doc = Incremental::new("(a)[b(c)]", classify);
+    assert_eq!(pairs(doc.blocks().unwrap()), [(0, 2), (3, 8), (5, 7)]);
+    
+    let changes = doc.insert(4, "()").unwrap();
+    assert_eq!(pairs(doc.blocks().unwrap()), [(0, 2), (3, 10), (4, 5), (7, 9)]);
+    assert_eq!(pairs(&changes.created), [(4, 5)]);
+    assert_eq!(pairs(&changes.destroyed), []);
+    assert_eq!(pairs(&changes.shifted), [(3, 10), (7, 9)]);
+    
+    let changes = doc.delete(0..2).unwrap_err();
+    assert!(matches!(changes, blocks::BalanceBlockError::ExtraRight { closing: 0 }));
+    assert!(doc.blocks().is_err());
+    
+    let changes = doc.insert(0, "(").unwrap();
+    assert_eq!(pairs(doc.blocks().unwrap()), [(0, 1), (2, 9), (3, 4), (6, 8)]);
+    assert_eq!(changes.created.len(), 4);
+}
+
+#[test]
+fn random_edits_match_rescan() {
+    let mut rng = Rng(0x9e3779b97f4a7c15);
+    // Mostly balanced snippets, so that most edits take the incremental path
+    let snippets = ["", "x", "()", "[x]", "([])", "", "x", "(", "]"];
+    
+    for _ in 0..200 {
+        let mut text = String::from("([x]([]))");
+        let mut doc = Incremental::new(&text, classify);
+        
+        for _ in 0..40 {
+            let old: Vec<_> = doc.blocks().map(pairs).unwrap_or_default();
+            let start = rng.below(text.len() + 1);
+            let end = match rng.below(8) {
+                0 => start + rng.below(text.len() - start + 1),
+                _ => start + usize::from(text[start..].starts_with('x'))
+            };
+            let inserted = snippets[rng.below(snippets.len())];
+            
+            let removed = text[start..end].to_owned();
+            let changes = doc.replace(start..end, inserted);
+            text.replace_range(start..end, inserted);
+            
+            let fresh = Incremental::new(&text, classify);
+            let expected = fresh.blocks().map(pairs).ok();
+            assert_eq!(doc.blocks().map(pairs).ok(), expected, "text {text:?}");
+            
+            let Ok(changes) = changes else {
+                // Undo the edit to get back to a balanced document
+                let _ = doc.replace(start..start + inserted.len(), &removed);
+                text.replace_range(start..start + inserted.len(), &removed);
+                assert_eq!(doc.blocks().map(pairs).ok(), Incremental::new(&text, classify).blocks().map(pairs).ok());
+                continue;
+            };
+            
+            // Surviving blocks are the old ones minus the destroyed ones, moved by the edit
+            let delta = inserted.len() as isize - (end - start) as isize;
+            let shift = |idx: usize| if idx >= end { (idx as isize + delta) as usize } else { idx };
+            let destroyed = pairs(&changes.destroyed);
+            let mut rebuilt: Vec<_> = old
+                .into_iter()
+                .filter(|pair| !destroyed.contains(pair))
+                .map(|(opening, closing)| (shift(opening), shift(closing)))
+                .chain(pairs(&changes.created))
+                .collect();
+            rebuilt.sort();
+            
+            assert_eq!(Some(rebuilt), expected, "text {text:?}");
+            assert!(pairs(&changes.shifted).iter().all(|pair| expected.as_ref().unwrap().contains(pair)));
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use blocks::{BalanceBlockError, BalanceExt, Blocks, Delimiters};
+use common::classify;
+
+#[test]
+fn balance_pipeline() {
+    let blocks = "f(a[0], b)".char_indices().balance(classify).unwrap();
+    let pairs: Vec<_> = blocks.iter().map(|block| (block.opening(), block.closing(), *block.kind())).collect();
+    
+    assert_eq!(pairs, [(1, 9, '('), (3, 5, '[')]);
+    assert!(matches!(
+        "(]".char_indices().balance(classify),
+        Err(BalanceBlockError::Mismatch {expected: '(', found: '[', opening: 0, closing: 1})
+    ));
+    assert!(matches!("x)".char_indices().balance(classify), Err(BalanceBlockError::ExtraRight {closing: 1})));
+}
+
+#[test]
+fn collect_events() {
+    let events = |code: &'static str| code.char_indices().filter_map(|(n, c)| Some((n, classify(c)?)));
+    
+    let blocks: Blocks<char> = events("([])").collect();
+    assert!(blocks.is_valid());
+    assert_eq!(blocks.consume().unwrap().len(), 2);
+    
+    // The first error is latched and later tokens are ignored
+    let mut blocks: Blocks<char> = events("(]").collect();
+    blocks.extend(events("x)"));
+    assert!(!blocks.is_valid());
+    assert!(matches!(blocks.consume(), Err(BalanceBlockError::Mismatch {closing: 1, ..})));
+    
+    let blocks: Blocks<char> = events("((").collect();
+    assert!(matches!(blocks.consume(), Err(BalanceBlockError::ExtraLeft {unclosed, ..}) if unclosed == [0, 1]));
+}
+
+#[test]
+fn collect_token_ranges() {
+    let code = "begin (x) end";
+    let delimiters = Delimiters::new().pair("begin", "end").pair('(', ')');
+    let blocks: Blocks<usize> = delimiters.tokens(code).collect();
+    let blocks = blocks.consume().unwrap();
+    
+    assert_eq!(blocks[0].closing_token(), 10..13);
+    assert_eq!(blocks[1].opening_token(), 6..7);
+}
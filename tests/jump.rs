@@ -0,0 +1,57 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{Blocks, JumpTable};
+
+fn table(code: &[u8]) -> JumpTable {
+    let mut blocks = Blocks::new();
+    
+    for (n, &c) in code.iter().enumerate() {
+        match c {
+            b'[' => blocks.add_left(n).unwrap(),
+            b']' => blocks.add_right(n).unwrap(),
+            _ => {}
+        }
+    }
+    
+    blocks.into_jump_table().unwrap()
+}
+
+#[test]
+fn partners() {
+    //                  0123456789
+    let table = table(b"[-[+]][].");
+    
+    assert_eq!(table.len(), 8);
+    assert_eq!(table.partner(0), Some(5));
+    assert_eq!(table.partner(5), Some(0));
+    assert_eq!(table.partner(2), Some(4));
+    assert_eq!(table.partner(4), Some(2));
+    assert_eq!(table.partner(6), Some(7));
+    assert_eq!(table.partner(1), None);
+    assert_eq!(table.partner(8), None);
+}
+
+#[test]
+fn brainfuck() {
+    // Multiplies 6 by 7 into the second cell
+    let code = b"++++++[>+++++++<-]";
+    let table = table(code);
+    let (mut pc, mut ptr, mut cells) = (0, 0, [0u8; 2]);
+    
+    while pc < code.len() {
+        match code[pc] {
+            b'+' => cells[ptr] += 1,
+            b'-' => cells[ptr] -= 1,
+            b'>' => ptr += 1,
+            b'<' => ptr -= 1,
+            // SAFETY: `pc` holds a bracket of the table
+            b'[' if cells[ptr] == 0 => pc = unsafe {table.partner_unchecked(pc)},
+            b']' if cells[ptr] != 0 => pc = unsafe {table.partner_unchecked(pc)},
+            _ => {}
+        }
+        
+        pc += 1;
+    }
+    
+    assert_eq!(cells, [0, 42]);
+}
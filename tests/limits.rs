@@ -0,0 +1,102 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{BalanceBlockError, Blocks, Delimiters, Limits, Recovering, RevBlocks, StrayPolicy};
+
+#[test]
+fn depth_limit() {
+    let mut blocks = Blocks::with_limits(Limits::new().max_depth(2));
+    
+    blocks.add_left(0).unwrap();
+    blocks.add_left(1).unwrap();
+    assert!(matches!(blocks.add_left(2), Err(BalanceBlockError::DepthExceeded {opening: 2, limit: 2})));
+    
+    // Siblings do not add to the depth
+    blocks.add_right(3).unwrap();
+    blocks.add_left(4).unwrap();
+    blocks.add_right(5).unwrap();
+    blocks.add_right(6).unwrap();
+    assert_eq!(blocks.consume().unwrap().len(), 3);
+}
+
+#[test]
+fn block_limit() {
+    let mut blocks = Blocks::with_limits(Limits::new().max_blocks(2).max_depth(10));
+    blocks.extend(Delimiters::brackets().tokens("()[]{}"));
+    
+    assert!(matches!(blocks.consume(), Err(BalanceBlockError::BlocksExceeded {opening: 4, limit: 2})));
+}
+
+#[test]
+fn memory_limit() {
+    let limits = Limits::new().max_memory(1024);
+    let mut blocks = Blocks::with_limits(limits);
+    let result = (0..).try_for_each(|n| blocks.add_left(n));
+    
+    match result {
+        Err(BalanceBlockError::MemoryExceeded {opening, limit: 1024}) => {
+            assert!(opening > 0);
+            assert_eq!(blocks.unclosed().count(), opening);
+        }
+        other => panic!("unexpected {other:?}")
+    }
+    assert_eq!(blocks.limits(), &limits);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn limited_stream() {
+    use blocks::{ChunkedBlocks, Event};
+    
+    let classify = |byte| match byte {
+        b'[' => Some(Event::Open(())),
+        b']' => Some(Event::Close(())),
+        _ => None
+    };
+    let mut stream = ChunkedBlocks::with_limits(Limits::new().max_depth(3), classify);
+    
+    stream.feed(b"[[[]]]").unwrap();
+    assert!(matches!(stream.feed(b"[[[["), Err(BalanceBlockError::DepthExceeded {opening: 9, limit: 3})));
+}
+
+#[test]
+fn limited_recovery() {
+    let limits = Limits::new().max_depth(2);
+    let mut recovering = Recovering::with_limits(StrayPolicy::Error, limits);
+    
+    // The opening token past the limit is skipped, so its closing token is stray
+    for (n, c) in "[[[]]]".char_indices() {
+        match c {
+            '[' => recovering.add_left(n),
+            _ => recovering.add_right(n)
+        }
+    }
+    
+    assert_eq!(recovering.limits(), &limits);
+    
+    let recovered = recovering.finish();
+    
+    assert_eq!(recovered.blocks.len(), 2);
+    assert!(matches!(
+        &recovered.errors[..],
+        [
+            BalanceBlockError::DepthExceeded {opening: 2, limit: 2},
+            BalanceBlockError::ExtraRight {closing: 5}
+        ]
+    ));
+}
+
+#[test]
+fn limited_right_to_left() {
+    let mut rev = RevBlocks::with_limits(Limits::new().max_depth(2).max_blocks(3));
+    
+    rev.add_right(9).unwrap();
+    rev.add_right(8).unwrap();
+    assert!(matches!(rev.add_right(7), Err(BalanceBlockError::DepthExceeded {opening: 7, limit: 2})));
+    
+    rev.add_left(6).unwrap();
+    rev.add_right(5).unwrap();
+    rev.add_left(4).unwrap();
+    rev.add_left(3).unwrap();
+    assert!(matches!(rev.add_right(2), Err(BalanceBlockError::BlocksExceeded {opening: 2, limit: 3})));
+    assert_eq!(rev.consume().unwrap().len(), 3);
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{Blocks, Columns, Delimiters, LineCol, LineIndex, Span};
+
+#[test]
+fn lines_and_columns() {
+    let text = "fn f() {\n    g(x)\n}";
+    let index = LineIndex::new(text);
+    
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.line(2), Some("    g(x)"));
+    assert_eq!(index.line(4), None);
+    assert_eq!(index.line(0), None);
+    assert_eq!(index.line_col(0), LineCol::new(1, 1));
+    assert_eq!(index.line_col(8), LineCol::new(1, 9));
+    assert_eq!(index.line_col(9), LineCol::new(2, 1));
+    assert_eq!(index.line_col(text.len()), LineCol::new(3, 2));
+    assert_eq!(LineIndex::new("").line_col(0), LineCol::new(1, 1));
+    assert_eq!(LineIndex::new("a\n").line(2), Some(""));
+}
+
+#[test]
+fn column_units() {
+    // 'é' is 2 bytes and 1 UTF-16 unit, '𝄞' is 4 bytes and 2 UTF-16 units
+    let text = "é𝄞(";
+    let offset = text.find('(').unwrap();
+    let col = |columns| LineIndex::new(text).columns(columns).line_col(offset).col;
+    
+    assert_eq!(col(Columns::Utf8), 7);
+    assert_eq!(col(Columns::Utf16), 4);
+    assert_eq!(col(Columns::Char), 3);
+}
+
+#[test]
+fn tab_stops() {
+    let text = "\tx\ty\n ab\tc";
+    let index = LineIndex::new(text).tab_width(4);
+    
+    assert_eq!(index.line_col(1), LineCol::new(1, 5));
+    assert_eq!(index.line_col(3), LineCol::new(1, 9));
+    assert_eq!(index.line_col(text.len() - 1), LineCol::new(2, 5));
+    assert_eq!(LineIndex::new(text).line_col(3), LineCol::new(1, 4));
+}
+
+#[test]
+fn block_spans() {
+    let text = "begin\n  (x)\nend";
+    let delimiters = Delimiters::new().pair("begin", "end").pair('(', ')');
+    let blocks = Blocks::scan(text, &delimiters).unwrap();
+    let index = LineIndex::new(text);
+    
+    assert_eq!(blocks[0].span(&index), Span::new(LineCol::new(1, 1), LineCol::new(3, 4)));
+    assert_eq!(blocks[0].opening_span(&index), Span::new(LineCol::new(1, 1), LineCol::new(1, 6)));
+    assert_eq!(blocks[0].closing_span(&index), Span::new(LineCol::new(3, 1), LineCol::new(3, 4)));
+    assert_eq!(blocks[1].span(&index).to_string(), "2:3-2:6");
+}
@@ -0,0 +1,76 @@
+//! Peak memory use under a memory limit, measured by a counting allocator
+
+#![cfg(feature = "std")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use blocks::{BalanceBlockError, Blocks, ByteDelimiters, Limits};
+
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        // SAFETY: forwarded with the caller's layout
+        unsafe {System.alloc(layout)}
+    }
+    
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        // SAFETY: forwarded with the caller's pointer and layout
+        unsafe {System.dealloc(ptr, layout)}
+    }
+    
+    /// Count the net growth only, the limit bounding the capacity held rather than the copies while moving it
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        let current = match size >= layout.size() {
+            true => CURRENT.fetch_add(size - layout.size(), Ordering::Relaxed) + size - layout.size(),
+            false => CURRENT.fetch_sub(layout.size() - size, Ordering::Relaxed) - (layout.size() - size)
+        };
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        // SAFETY: forwarded with the caller's pointer and layout
+        unsafe {System.realloc(ptr, layout, size)}
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Run `f` and return the growth of the peak memory use during it
+fn peak(f: impl FnOnce()) -> usize {
+    let start = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(start, Ordering::Relaxed);
+    f();
+    PEAK.load(Ordering::Relaxed) - start
+}
+
+#[test]
+fn memory_limit_bounds_the_capacity() {
+    const LIMIT: usize = 1 << 16;
+    
+    let bytes = "[]".repeat(1 << 14) + &"[".repeat(1 << 16);
+    let limits = Limits::new().max_memory(LIMIT);
+    let delimiters = ByteDelimiters::brackets();
+    
+    let sequential = peak(|| {
+        let mut blocks = Blocks::with_limits(limits);
+        let result = delimiters.try_find(bytes.as_bytes(), |idx, event| blocks.add(idx, event));
+        
+        assert!(matches!(result, Err(BalanceBlockError::MemoryExceeded {limit: LIMIT, ..})));
+    });
+    assert!(sequential <= LIMIT, "{sequential} bytes");
+    
+    // Spawning the threads takes a few kilobytes on top of the blocks
+    let parallel = peak(|| {
+        let threads = NonZeroUsize::new(8).unwrap();
+        let result = Blocks::balance_parallel_with_limits(bytes.as_bytes(), threads, limits, |byte| delimiters.classify(byte));
+        
+        assert!(matches!(result, Err(BalanceBlockError::MemoryExceeded {limit: LIMIT, ..})));
+    });
+    assert!(parallel <= LIMIT + 8192, "{parallel} bytes");
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "std")]
+
+use std::num::NonZeroUsize;
+mod common;
+
+use blocks::{BalanceBlockError, Balanced, Block, ByteDelimiters, Blocks, Limits};
+use common::{classify_byte as classify, Rng};
+
+fn sequential(bytes: &[u8], limits: Limits) -> Result<Vec<Block<Balanced, char>>, BalanceBlockError<char>> {
+    let mut blocks = Blocks::with_limits(limits);
+    
+    for (n, &byte) in bytes.iter().enumerate() {
+        if let Some(event) = classify(byte) {
+            blocks.add(n, event)?;
+        }
+    }
+    
+    blocks.consume()
+}
+
+/// Compare outcomes through their debug representation, errors included
+fn outcome<K: std::fmt::Debug>(result: Result<Vec<Block<Balanced, K>>, BalanceBlockError<K>>) -> String {
+    format!("{result:?}")
+}
+
+/** Mostly balanced input with the occasional stray, mismatched or missing token,
+    spread by runs of filler over chunks long enough to be balanced on their own */
+fn input(rng: &mut Rng, events: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut open = Vec::new();
+    
+    for _ in 0..events {
+        match rng.next() % 16 {
+            0..=4 => {
+                let (left, right) = [(b'(', b')'), (b'[', b']')][rng.next() as usize % 2];
+                bytes.push(left);
+                open.push(right);
+            }
+            5..=9 => bytes.extend(open.pop()),
+            10 if rng.next() % 8 == 0 => bytes.push(b")]"[rng.next() as usize % 2]),
+            _ => bytes.resize(bytes.len() + rng.next() as usize % 1024, b'x')
+        }
+    }
+    
+    if rng.next() % 2 == 0 {
+        bytes.extend(open.into_iter().rev());
+    }
+    
+    bytes
+}
+
+#[test]
+fn agrees_with_sequential() {
+    let mut rng = Rng(0x9e3779b97f4a7c15);
+    
+    for round in 0..400 {
+        let bytes = input(&mut rng, round % 97);
+        let expected = outcome(sequential(&bytes, Limits::new()));
+        
+        for threads in [1, 2, 3, 7, 64] {
+            let threads = NonZeroUsize::new(threads).unwrap();
+            
+            assert_eq!(
+                outcome(Blocks::balance_parallel(&bytes, threads, classify)),
+                expected,
+                "{} on {threads} threads",
+                String::from_utf8_lossy(&bytes)
+            );
+        }
+    }
+}
+
+#[test]
+fn limited_agrees_with_sequential() {
+    let mut rng = Rng(0x2545f4914f6cdd1d);
+    let limits = [
+        Limits::new().max_depth(2),
+        Limits::new().max_blocks(5),
+        Limits::new().max_memory(256),
+        Limits::new().max_depth(3).max_blocks(8).max_memory(400)
+    ];
+    
+    for round in 0..400 {
+        let bytes = input(&mut rng, round % 97);
+        
+        for limits in limits {
+            let expected = outcome(sequential(&bytes, limits));
+            
+            for threads in [1, 2, 3, 7, 64] {
+                let threads = NonZeroUsize::new(threads).unwrap();
+                let result = Blocks::balance_parallel_with_limits(&bytes, threads, limits, classify);
+                
+                // Chunks share the memory, so that they may run out of it sooner than a sequential scan
+                if limits.memory().is_some() && matches!(result, Err(BalanceBlockError::MemoryExceeded {..})) {
+                    continue;
+                }
+                
+                assert_eq!(
+                    outcome(result),
+                    expected,
+                    "{} on {threads} threads within {limits:?}",
+                    String::from_utf8_lossy(&bytes)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn reports_the_first_error() {
+    let threads = NonZeroUsize::new(4).unwrap();
+    
+    let x = "x".repeat(4096);
+    
+    // A stray closer in the second chunk precedes a mismatch in the third one
+    let bytes = format!("(((({x}))))){x}[)(");
+    assert!(matches!(
+        Blocks::balance_parallel(bytes.as_bytes(), threads, classify),
+        Err(BalanceBlockError::ExtraRight {closing: 4104})
+    ));
+    
+    // Blocks left open by every chunk are reported from the outermost
+    let bytes = format!("({x}({x}[{x}(");
+    match Blocks::balance_parallel(bytes.as_bytes(), threads, classify) {
+        Err(BalanceBlockError::ExtraLeft {unclosed, ..}) => assert_eq!(unclosed, [0, 4097, 8194, 12291]),
+        other => panic!("unexpected {other:?}")
+    }
+}
+
+#[test]
+fn scan_bytes_parallel_agrees_with_scan_bytes() {
+    let json = format!("[{}]", r#"{"a": [1, 2, {"b": [[], {}]}], "c": {"d": [3]}},"#.repeat(500));
+    let json = json.replace(",]", "]");
+    let delimiters = ByteDelimiters::brackets();
+    
+    for threads in [1, 5, 16] {
+        let threads = NonZeroUsize::new(threads).unwrap();
+        
+        assert_eq!(
+            outcome(Blocks::scan_bytes_parallel(json.as_bytes(), &delimiters, threads)),
+            outcome(Blocks::scan_bytes(json.as_bytes(), &delimiters))
+        );
+        assert_eq!(
+            outcome(Blocks::scan_bytes_parallel(&json.as_bytes()[1..], &delimiters, threads)),
+            outcome(Blocks::scan_bytes(&json.as_bytes()[1..], &delimiters))
+        );
+    }
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{BalanceBlockError, Recovering, StrayPolicy};
+
+fn run(code: &str, policy: StrayPolicy) -> blocks::Recovered<char> {
+    let mut recovering = Recovering::new(policy);
+    
+    for (n, c) in code.char_indices() {
+        match c {
+            '(' | '[' | '{' => recovering.add_left_kind(n, c),
+            ')' => recovering.add_right_kind(n, '('),
+            ']' => recovering.add_right_kind(n, '['),
+            '}' => recovering.add_right_kind(n, '{'),
+            _ => {}
+        }
+    }
+    
+    recovering.finish()
+}
+
+fn pairs(recovered: &blocks::Recovered<char>) -> Vec<(usize, usize)> {
+    recovered.blocks
+        .iter()
+        .map(|block| (block.opening(), block.closing()))
+        .collect()
+}
+
+#[test]
+fn collects_every_error() {
+    let recovered = run("]()]{", StrayPolicy::Error);
+    
+    assert_eq!(pairs(&recovered), [(1, 2)]);
+    assert!(matches!(
+        &recovered.errors[..],
+        [
+            BalanceBlockError::ExtraRight { closing: 0 },
+            BalanceBlockError::ExtraRight { closing: 3 },
+            BalanceBlockError::ExtraLeft { unclosed, .. }
+        ] if unclosed == &[4]
+    ));
+}
+
+#[test]
+fn stray_policies() {
+    let ignored = run("())", StrayPolicy::Ignore);
+    assert!(ignored.is_ok());
+    assert!(ignored.literals.is_empty());
+    
+    let literal = run("())", StrayPolicy::Literal);
+    assert!(literal.is_ok());
+    assert_eq!(literal.literals, [2]);
+    assert_eq!(pairs(&literal), [(0, 1)]);
+}
+
+#[test]
+fn closes_enclosing_block_on_mismatch() {
+    let recovered = run("{([}", StrayPolicy::Error);
+    
+    assert_eq!(pairs(&recovered), [(0, 3)]);
+    assert!(matches!(
+        &recovered.errors[..],
+        [
+            BalanceBlockError::Mismatch { expected: '[', found: '{', opening: 2, closing: 3 },
+            BalanceBlockError::ExtraLeft { unclosed, .. }
+        ] if unclosed == &[1]
+    ));
+}
+
+#[test]
+fn skips_unmatched_kind() {
+    let recovered = run("(])", StrayPolicy::Error);
+    
+    assert_eq!(pairs(&recovered), [(0, 2)]);
+    assert!(matches!(
+        &recovered.errors[..],
+        [BalanceBlockError::Mismatch { expected: '(', found: '[', opening: 0, closing: 1 }]
+    ));
+}
+
+#[test]
+fn unmatched_kind_follows_policy() {
+    let ignored = run("(])", StrayPolicy::Ignore);
+    assert!(ignored.is_ok());
+    assert!(ignored.literals.is_empty());
+    assert_eq!(pairs(&ignored), [(0, 2)]);
+    
+    let literal = run("(])", StrayPolicy::Literal);
+    assert!(literal.is_ok());
+    assert_eq!(literal.literals, [1]);
+    assert_eq!(pairs(&literal), [(0, 2)]);
+}
+
+#[test]
+fn multi_character_tokens() {
+    //          0123456789012345678
+    let code = "begin ( end ) end";
+    let mut recovering = Recovering::new(StrayPolicy::Literal);
+    
+    recovering.add_left_token(0..5, "begin");
+    recovering.add_left_token(6..7, "(");
+    recovering.add_right_token(8..11, "begin");
+    recovering.add_right_token(12..13, "(");
+    recovering.add_right_token(14..17, "begin");
+    
+    let recovered = recovering.finish();
+    let block = &recovered.blocks[0];
+    
+    assert_eq!(recovered.blocks.len(), 1);
+    assert_eq!(block.slice_outer(code), "begin ( end");
+    assert_eq!(block.closing_token(), 8..11);
+    assert_eq!(recovered.literals, [12, 14]);
+    assert!(matches!(
+        &recovered.errors[..],
+        [BalanceBlockError::Mismatch { expected: "(", found: "begin", opening: 6, closing: 8 }]
+    ));
+}
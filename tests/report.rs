@@ -0,0 +1,122 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{BalanceBlockError, Blocks, Delimiters, LineIndex};
+
+fn scan(code: &str) -> BalanceBlockError<usize> {
+    Blocks::scan(code, &Delimiters::brackets()).unwrap_err()
+}
+
+#[test]
+fn mismatch() {
+    let code = "fn f() {\n    g(x];\n}";
+    let index = LineIndex::new(code);
+    let report = scan(code).report(&index).path("src/f.rs").to_string();
+    
+    assert_eq!(report, "\
+error: mismatched closing token
+ --> src/f.rs:2:8
+  |
+2 |     g(x];
+  |      - block opened by `(` here
+  |        ^ `]` does not close `(`
+");
+}
+
+#[test]
+fn extra_right() {
+    let code = "a)";
+    let index = LineIndex::new(code);
+    
+    assert_eq!(scan(code).report(&index).to_string(), "\
+error: unbalanced closing token
+ --> 1:2
+  |
+1 | a)
+  |  ^ no open block to close
+");
+}
+
+#[test]
+fn extra_left_across_lines() {
+    let code = format!("{{\n\t[{}\n(", "\n".repeat(9));
+    let index = LineIndex::new(&code);
+    
+    assert_eq!(scan(&code).report(&index).to_string(), "\
+error: 3 unclosed blocks
+  --> 12:1
+   |
+ 1 | {
+   | - enclosing block is never closed either
+ 2 | \t[
+   | \t- enclosing block is never closed either
+...
+12 | (
+   | ^ this block is never closed
+");
+}
+
+#[test]
+fn colors() {
+    let code = "(";
+    let index = LineIndex::new(code);
+    let error = scan(code);
+    
+    assert!(error.report(&index).color(true).to_string().contains("\x1b[1;31m^ this block is never closed\x1b[0m"));
+    assert!(!error.report(&index).to_string().contains('\x1b'));
+    assert_eq!(
+        BalanceBlockError::<()>::CapacityExceeded {capacity: 4}.report(&index).to_string(),
+        "error: exceeded the capacity of 4 blocks\n"
+    );
+}
+
+#[test]
+fn multi_character_tokens() {
+    let delimiters = Delimiters::new().pair("begin", "end").pair('(', ')');
+    let report = |code| Blocks::scan(code, &delimiters).unwrap_err().report(&LineIndex::new(code)).to_string();
+    
+    assert_eq!(report("begin\n  x)"), "\
+error: mismatched closing token
+ --> 2:4
+  |
+1 | begin
+  | ----- block opened by `begin` here
+2 |   x)
+  |    ^ `)` does not close `begin`
+");
+    assert_eq!(report("f(x end"), "\
+error: mismatched closing token
+ --> 1:5
+  |
+1 | f(x end
+  |  - block opened by `(` here
+  |     ^^^ `end` does not close `(`
+");
+    
+    // Symbols are measured by the delimiters, if given
+    let code = "<% a %) %>";
+    let delimiters = Delimiters::new().pair("<%", "%>").pair('(', ')');
+    let error = Blocks::scan(code, &delimiters).unwrap_err();
+    let index = LineIndex::new(code);
+    
+    assert_eq!(error.report(&index).delimiters(&delimiters).to_string(), "\
+error: mismatched closing token
+ --> 1:7
+  |
+1 | <% a %) %>
+  | -- block opened by `<%` here
+  |       ^ `)` does not close `<%`
+");
+}
+
+#[test]
+fn offsets_past_the_end() {
+    let code = "a)";
+    let index = LineIndex::new(code);
+    
+    assert_eq!(BalanceBlockError::<usize>::ExtraRight {closing: 10}.report(&index).to_string(), "error: unbalanced closing token\n");
+    
+    // The opening label is not shown without the offending token
+    let error = BalanceBlockError::Mismatch {expected: 0, found: 1, opening: 0, closing: 20};
+    
+    assert_eq!(error.report(&index).to_string(), "error: mismatched closing token\n");
+}
@@ -0,0 +1,149 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{BalanceBlockError, Balanced, Block, Blocks, Delimiters};
+
+fn triples(blocks: &[Block<Balanced, usize>]) -> Vec<(usize, usize, usize)> {
+    blocks.iter().map(|block| (block.opening(), block.closing(), *block.kind())).collect()
+}
+
+#[test]
+fn skips_other_characters() {
+    let blocks = Blocks::scan("f(x) = [λ, {y}]", &Delimiters::brackets()).unwrap();
+    
+    assert_eq!(triples(&blocks), [(1, 3, 0), (7, 15, 1), (12, 14, 2)]);
+}
+
+#[test]
+fn char_offsets() {
+    let blocks = Blocks::scan_chars("f(x) = [λ, {y}]", &Delimiters::brackets()).unwrap();
+    
+    assert_eq!(triples(&blocks), [(1, 3, 0), (7, 14, 1), (11, 13, 2)]);
+}
+
+#[test]
+fn custom_pairs() {
+    let delimiters = Delimiters::new().pair('<', '>').pair('«', '»');
+    let blocks = Blocks::scan("«a<b>»()", &delimiters).unwrap();
+    
+    assert_eq!(triples(&blocks), [(0, 6, 1), (3, 5, 0)]);
+    assert!(matches!(
+        Blocks::scan("<»", &delimiters),
+        Err(BalanceBlockError::Mismatch { expected: 0, found: 1, opening: 0, closing: 1 })
+    ));
+}
+
+fn rust() -> Delimiters {
+    Delimiters::brackets()
+        .string('"', Some('\\'))
+        .line_comment("//")
+        .nested_block_comment("/*", "*/")
+}
+
+#[test]
+fn ignores_strings_and_comments() {
+    let code = "f(\"(\", \"\\\")\") // (\n/* [ /* { */ ] */ g[0]";
+    let blocks = Blocks::scan(code, &rust()).unwrap();
+    
+    assert_eq!(triples(&blocks), [(1, 12, 0), (38, 40, 1)]);
+}
+
+#[test]
+fn flat_block_comments() {
+    let delimiters = Delimiters::brackets().block_comment("/*", "*/");
+    
+    // The first `*/` ends the comment, leaving a stray `]`
+    assert!(matches!(
+        Blocks::scan("/* /* */ ] */", &delimiters),
+        Err(BalanceBlockError::ExtraRight { closing: 9 })
+    ));
+}
+
+#[test]
+fn unterminated_regions_run_to_the_end() {
+    assert!(Blocks::scan("() \"(", &rust()).is_ok());
+    assert!(Blocks::scan("() /* /* */ (", &rust()).is_ok());
+    assert!(Blocks::scan("() // (", &rust()).is_ok());
+}
+
+#[test]
+fn char_offsets_skip_regions() {
+    let blocks = Blocks::scan_chars("\"λ(\" (λ)", &rust()).unwrap();
+    
+    assert_eq!(triples(&blocks), [(5, 7, 0)]);
+}
+
+#[test]
+fn multi_character_delimiters() {
+    let delimiters = Delimiters::new()
+        .pair("{{", "}}")
+        .pair('{', '}')
+        .pair("{%", "%}")
+        .pair("begin", "end");
+    //                0123456789012345678901
+    let text = "{{ {%a%} }}{ begin b end }";
+    let blocks = Blocks::scan(text, &delimiters).unwrap();
+    
+    assert_eq!(triples(&blocks), [(0, 9, 0), (3, 6, 2), (11, 25, 1), (13, 21, 3)]);
+    assert_eq!(blocks[0].opening_token(), 0..2);
+    assert_eq!(blocks[0].closing_token(), 9..11);
+    assert_eq!(blocks[3].opening_token(), 13..18);
+    assert_eq!(blocks[3].closing_token(), 21..24);
+    assert_eq!(&text[blocks[1].opening_token()], "{%");
+}
+
+#[test]
+fn multi_character_char_offsets() {
+    let delimiters = Delimiters::new().pair("<!--", "-->").string('"', None);
+    let blocks = Blocks::scan_chars("λ<!-- \"-->\" -->", &delimiters).unwrap();
+    
+    assert_eq!(blocks[0].opening_token(), 1..5);
+    assert_eq!(blocks[0].closing_token(), 12..15);
+}
+
+#[test]
+fn word_delimiters_match_whole_words() {
+    let delimiters = Delimiters::new().pair("begin", "end").pair('(', ')');
+    let blocks = Blocks::scan("begin append end", &delimiters).unwrap();
+    assert_eq!(triples(&blocks), [(0, 13, 0)]);
+    
+    //          0123456789012345678901234567890
+    let blocks = Blocks::scan("begin append(endx) begin_ end", &delimiters).unwrap();
+    assert_eq!(triples(&blocks), [(0, 26, 0), (12, 17, 1)]);
+    
+    let text = "begin append(x_end, ending) end";
+    let blocks = Blocks::scan(text, &delimiters).unwrap();
+    
+    assert_eq!(triples(&blocks), [(0, 28, 0), (12, 26, 1)]);
+    assert_eq!(delimiters.classify("end;"), Some((blocks::Event::Close(0), 3)));
+    assert_eq!(delimiters.classify("endx"), None);
+}
+
+#[test]
+#[should_panic(expected = "duplicate delimiter")]
+fn symmetric_delimiter() {
+    let _ = Delimiters::new().pair("|", "|");
+}
+
+#[test]
+#[should_panic(expected = "duplicate delimiter")]
+fn delimiter_reused_across_pairs() {
+    let _ = Delimiters::new().pair('a', 'b').pair('b', 'c');
+}
+
+#[test]
+#[should_panic(expected = "empty comment marker")]
+fn empty_line_comment() {
+    let _ = Delimiters::brackets().line_comment("");
+}
+
+#[test]
+#[should_panic(expected = "empty comment marker")]
+fn empty_block_comment() {
+    let _ = Delimiters::brackets().block_comment("/*", "");
+}
+
+#[test]
+#[should_panic(expected = "empty comment marker")]
+fn empty_nested_block_comment() {
+    let _ = Delimiters::brackets().nested_block_comment("", "*/");
+}
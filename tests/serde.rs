@@ -0,0 +1,115 @@
+#![cfg(all(feature = "serde", feature = "alloc"))]
+
+use blocks::{BalanceBlockError, Balanced, Block, Blocks, Event, Limits, Unbalanced};
+
+#[test]
+fn blocks_round_trip() {
+    let mut blocks = Blocks::new();
+    
+    for (n, c) in "[[][".char_indices() {
+        match c {
+            '[' => blocks.add_left_kind(n, c).unwrap(),
+            _ => blocks.add_right_kind(n, '[').unwrap()
+        }
+    }
+    
+    let json = serde_json::to_string(&blocks).unwrap();
+    let mut restored: Blocks<char> = serde_json::from_str(&json).unwrap();
+    
+    restored.add_right_kind(4, '[').unwrap();
+    restored.add_right_kind(5, '[').unwrap();
+    
+    let pairs: Vec<_> = restored
+        .consume()
+        .unwrap()
+        .iter()
+        .map(|block| (block.opening(), block.closing()))
+        .collect();
+    assert_eq!(pairs, [(0, 5), (1, 2), (3, 4)]);
+}
+
+#[test]
+fn rejects_inconsistent_blocks() {
+    let cases = [
+        // A block left open but not on the stack
+        r#"{"inner":[{"opening":0,"closing":null,"kind":null}],"open":[]}"#,
+        // Crossing blocks
+        r#"{"inner":[{"opening":0,"closing":2,"kind":null},{"opening":1,"closing":3,"kind":null}],"open":[]}"#,
+        // Blocks out of order
+        r#"{"inner":[{"opening":2,"closing":3,"kind":null},{"opening":0,"closing":1,"kind":null}],"open":[]}"#,
+        // A closed block on the stack
+        r#"{"inner":[{"opening":0,"closing":1,"kind":null}],"open":[0]}"#,
+    ];
+    
+    for json in cases {
+        assert!(serde_json::from_str::<Blocks>(json).is_err(), "accepted {json}");
+    }
+    
+    let json = r#"{"inner":[{"opening":0,"closing":3,"kind":null},{"opening":1,"closing":null,"kind":null}],"open":[1]}"#;
+    assert!(serde_json::from_str::<Blocks>(json).is_err());
+    
+    let json = r#"{"inner":[{"opening":0,"closing":null,"kind":null},{"opening":1,"closing":2,"kind":null}],"open":[0]}"#;
+    assert!(serde_json::from_str::<Blocks>(json).is_ok());
+}
+
+#[test]
+fn latched_error_round_trip() {
+    let blocks: Blocks = [(0, Event::Close(()))].into_iter().collect();
+    let json = serde_json::to_string(&blocks).unwrap();
+    let restored: Blocks = serde_json::from_str(&json).unwrap();
+    
+    assert!(matches!(restored.consume(), Err(BalanceBlockError::ExtraRight {closing: 0})));
+}
+
+#[test]
+fn limits_round_trip() {
+    let mut blocks = Blocks::with_limits(Limits::new().max_depth(1));
+    blocks.add_left(0).unwrap();
+    
+    let json = serde_json::to_string(&blocks).unwrap();
+    let mut restored: Blocks = serde_json::from_str(&json).unwrap();
+    assert!(matches!(restored.add_left(1), Err(BalanceBlockError::DepthExceeded {opening: 1, limit: 1})));
+    
+    let json = r#"{"inner":[{"opening":0,"closing":null,"kind":null}],"open":[0],"limits":{"depth":0,"blocks":null,"memory":null}}"#;
+    assert!(serde_json::from_str::<Blocks>(json).is_err());
+}
+
+#[test]
+fn rejects_out_of_bounds_and_overlapping_tokens() {
+    let json = r#"{"opening":0,"closing":5,"kind":null,"closing_len":18446744073709551615}"#;
+    assert!(serde_json::from_str::<Block<Balanced>>(json).is_err());
+    assert!(serde_json::from_str::<Block<Unbalanced>>(json).is_err());
+    
+    let json = r#"{"opening":0,"closing":5,"kind":null,"opening_len":5,"closing_len":3}"#;
+    assert!(serde_json::from_str::<Block<Balanced>>(json).is_ok());
+    
+    // The opening token of the first block covers the second block
+    let json = r#"{"inner":[{"opening":0,"closing":null,"kind":null,"opening_len":5},{"opening":1,"closing":null,"kind":null}],"open":[0,1]}"#;
+    assert!(serde_json::from_str::<Blocks>(json).is_err());
+    
+    // The closing token of the inner block runs into the outer one
+    let json = r#"{"inner":[{"opening":0,"closing":4,"kind":null},{"opening":1,"closing":2,"kind":null,"closing_len":3}],"open":[]}"#;
+    assert!(serde_json::from_str::<Blocks>(json).is_err());
+    
+    let json = r#"{"inner":[{"opening":0,"closing":5,"kind":null},{"opening":1,"closing":2,"kind":null,"closing_len":3}],"open":[]}"#;
+    assert!(serde_json::from_str::<Blocks>(json).is_ok());
+}
+
+#[test]
+fn rejects_backwards_blocks() {
+    assert!(serde_json::from_str::<Block<Balanced>>(r#"{"opening":1,"closing":4,"kind":null}"#).is_ok());
+    assert!(serde_json::from_str::<Block<Balanced>>(r#"{"opening":4,"closing":1,"kind":null}"#).is_err());
+    assert!(serde_json::from_str::<Block<Unbalanced>>(r#"{"opening":4,"closing":null,"kind":null}"#).is_ok());
+    assert!(serde_json::from_str::<Block<Unbalanced>>(r#"{"opening":4,"closing":4,"kind":null}"#).is_err());
+}
+
+#[test]
+fn error_round_trip() {
+    let err = BalanceBlockError::Mismatch {expected: '(', found: ']', opening: 0, closing: 1};
+    let json = serde_json::to_string(&err).unwrap();
+    
+    assert!(matches!(
+        serde_json::from_str(&json).unwrap(),
+        BalanceBlockError::Mismatch { expected: '(', found: ']', opening: 0, closing: 1 }
+    ));
+}
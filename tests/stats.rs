@@ -0,0 +1,75 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{BalanceBlockError, BlockTree, Blocks, Delimiters, Event, Limits, Stats};
+
+#[test]
+fn nesting_statistics() {
+    //          0123456789012345
+    let code = "(a[b]{c[d]}) [] ";
+    let blocks = Blocks::scan(code, &Delimiters::brackets()).unwrap();
+    let stats = Stats::new(&blocks, 5);
+    
+    assert_eq!(stats.blocks, 5);
+    assert_eq!(stats.max_depth, Some(2));
+    assert_eq!(stats.depths, [2, 2, 1]);
+    assert_eq!(stats.kinds.into_iter().collect::<Vec<_>>(), [(0, 1), (1, 3), (2, 1)]);
+    assert_eq!(stats.max_len, 12);
+    assert_eq!(stats.mean_len, (12 + 3 + 6 + 3 + 2) as f64 / 5.0);
+    
+    let largest: Vec<_> = stats.largest.iter().map(|block| block.opening()).collect();
+    assert_eq!(largest, [0, 13]);
+    assert_eq!(Stats::new(&blocks, 1).largest.len(), 1);
+    
+    // Depths are counted the same way as in a tree
+    let tree = BlockTree::new(blocks);
+    assert_eq!(stats.max_depth, tree.pre_order().map(|id| tree.depth(id)).max());
+}
+
+#[test]
+fn empty_statistics() {
+    let stats = Stats::<usize>::new(&[], 3);
+    
+    assert_eq!(stats.max_depth, None);
+    assert!(stats.depths.is_empty());
+    assert_eq!(stats.mean_len, 0.0);
+    assert!(Stats::<usize>::depth_profile(&[], 2) == [0, 0]);
+}
+
+#[test]
+fn depth_profile() {
+    let code = "a(b[c]){{}}";
+    let blocks = Blocks::scan(code, &Delimiters::brackets()).unwrap();
+    
+    assert_eq!(Stats::depth_profile(&blocks, code.len()), [0, 1, 1, 2, 2, 2, 1, 1, 2, 2, 1]);
+    assert_eq!(Stats::depth_profile(&blocks, 4), [0, 1, 1, 2]);
+    
+    let begin = Delimiters::new().pair("begin", "end");
+    let blocks = Blocks::scan("begin x end", &begin).unwrap();
+    assert_eq!(Stats::depth_profile(&blocks, 12), [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0]);
+}
+
+#[test]
+fn depths_against_the_depth_limit() {
+    let code = "(a[b]{c[d]}) [] ";
+    let blocks = Blocks::scan(code, &Delimiters::brackets()).unwrap();
+    let max_depth = Stats::new(&blocks, 0).max_depth.unwrap();
+    
+    // Stats count from 0, the profile and the limit count the top-level blocks as 1
+    assert_eq!(Stats::depth_profile(&blocks, code.len()).into_iter().max(), Some(max_depth + 1));
+    
+    let balance = |limit| {
+        let mut blocks = Blocks::with_limits(Limits::new().max_depth(limit));
+        
+        for (range, event) in Delimiters::brackets().tokens(code) {
+            match event {
+                Event::Open(kind) => blocks.add_left_token(range, kind)?,
+                Event::Close(kind) => blocks.add_right_token(range, kind)?
+            }
+        }
+        
+        blocks.consume()
+    };
+    
+    assert!(balance(max_depth + 1).is_ok());
+    assert!(matches!(balance(max_depth), Err(BalanceBlockError::DepthExceeded {opening: 7, limit: 2})));
+}
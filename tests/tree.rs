@@ -0,0 +1,144 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{BlockTree, Blocks, NodeId};
+
+fn tree(code: &str) -> BlockTree {
+    let mut blocks = Blocks::new();
+    
+    for (n, c) in code.char_indices() {
+        match c {
+            '[' => blocks.add_left(n).unwrap(),
+            ']' => blocks.add_right(n).unwrap(),
+            _ => {}
+        }
+    }
+    
+    blocks.into_tree().unwrap()
+}
+
+fn openings(tree: &BlockTree, ids: impl Iterator<Item = NodeId>) -> Vec<usize> {
+    ids.map(|id| tree.get(id).opening()).collect()
+}
+
+#[test]
+fn navigation() {
+    //                  0123456789
+    let tree = tree("[[][]][[]]");
+    let ids: Vec<_> = tree.pre_order().collect();
+    
+    assert_eq!(openings(&tree, tree.roots()), [0, 6]);
+    assert_eq!(openings(&tree, tree.children(ids[0])), [1, 3]);
+    assert_eq!(openings(&tree, tree.children(ids[1])), []);
+    assert_eq!(tree.parent(ids[2]), Some(ids[0]));
+    assert_eq!(tree.parent(ids[3]), None);
+    assert_eq!(tree.next_sibling(ids[1]), Some(ids[2]));
+    assert_eq!(tree.prev_sibling(ids[2]), Some(ids[1]));
+    assert_eq!(tree.next_sibling(ids[0]), Some(ids[3]));
+    assert_eq!(tree.prev_sibling(ids[0]), None);
+    assert_eq!(tree.depth(ids[4]), 1);
+    assert_eq!(openings(&tree, tree.pre_order()), [0, 1, 3, 6, 7]);
+    assert_eq!(openings(&tree, tree.post_order()), [1, 3, 0, 7, 6]);
+}
+
+#[test]
+fn empty() {
+    let tree = tree("");
+    
+    assert!(tree.is_empty());
+    assert_eq!(tree.roots().count(), 0);
+    assert_eq!(tree.post_order().count(), 0);
+}
+
+/// Every balanced string over `[` and `]` of length up to 12
+fn balanced_strings() -> impl Iterator<Item = String> {
+    (0..=12).flat_map(|len| {
+        (0u32..1 << len).filter_map(move |bits| {
+            let code: String = (0..len)
+                .map(|i| if bits >> i & 1 == 0 { '[' } else { ']' })
+                .collect();
+            let mut depth = 0i32;
+            
+            code.chars()
+                .all(|c| {
+                    depth += if c == '[' { 1 } else { -1 };
+                    depth >= 0
+                })
+                .then_some(code)
+                .filter(|_| depth == 0)
+        })
+    })
+}
+
+#[test]
+fn matches_naive_nesting() {
+    for code in balanced_strings() {
+        let tree = tree(&code);
+        let blocks = tree.blocks();
+        
+        for id in tree.pre_order() {
+            let block = tree.get(id);
+            let ancestors: Vec<_> = (0..blocks.len())
+                .filter(|&i| blocks[i].opening() < block.opening() && block.closing() < blocks[i].closing())
+                .collect();
+            
+            assert_eq!(tree.depth(id), ancestors.len(), "input {code:?}");
+            assert_eq!(tree.parent(id).map(NodeId::index), ancestors.last().copied(), "input {code:?}");
+            
+            for child in tree.children(id) {
+                assert_eq!(tree.parent(child), Some(id), "input {code:?}");
+            }
+        }
+        
+        let mut post: Vec<_> = tree.post_order().collect();
+        post.sort();
+        assert!(post.into_iter().eq(tree.pre_order()), "input {code:?}");
+        
+        let closings: Vec<_> = tree.post_order().map(|id| tree.get(id).closing()).collect();
+        assert!(closings.windows(2).all(|w| w[0] < w[1]), "input {code:?}");
+    }
+}
+
+#[test]
+fn position_queries_match_naive() {
+    // Spacing out the tokens leaves positions outside of any delimiter
+    let spaced = balanced_strings().map(|code| code.chars().flat_map(|c| [c, ' ']).collect());
+    
+    for code in balanced_strings().chain(spaced) {
+        let tree = tree(&code);
+        let blocks = tree.blocks();
+        
+        for idx in 0..=code.len() {
+            let chain: Vec<_> = tree.enclosing_chain(idx).map(NodeId::index).collect();
+            let mut naive: Vec<_> = (0..blocks.len())
+                .filter(|&i| blocks[i].opening() <= idx && idx <= blocks[i].closing())
+                .collect();
+            naive.reverse();
+            
+            assert_eq!(chain, naive, "input {code:?} at {idx}");
+            assert_eq!(tree.innermost_containing(idx).map(NodeId::index), naive.first().copied());
+            assert_eq!(
+                tree.block_at_opening(idx).map(NodeId::index),
+                blocks.iter().position(|block| block.opening() == idx)
+            );
+            assert_eq!(
+                tree.block_at_closing(idx).map(NodeId::index),
+                blocks.iter().position(|block| block.closing() == idx)
+            );
+        }
+    }
+}
+
+#[test]
+fn wide_tokens_are_contained() {
+    let delimiters = blocks::Delimiters::new().pair("begin", "end");
+    //                                  0123456789012345678
+    let blocks = Blocks::scan("begin begin end end", &delimiters).unwrap();
+    let tree = BlockTree::new(blocks);
+    let innermost = |idx| tree.innermost_containing(idx).map(|id| tree.get(id).opening());
+    
+    assert_eq!(innermost(8), Some(6));
+    assert_eq!(innermost(14), Some(6));
+    assert_eq!(innermost(15), Some(0));
+    assert_eq!(innermost(18), Some(0));
+    assert_eq!(innermost(19), None);
+}
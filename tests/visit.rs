@@ -0,0 +1,78 @@
+#![cfg(feature = "alloc")]
+
+use blocks::{Balanced, Block, BlockTree, BlockVisitor, Blocks, Delimiters, Visit};
+
+/// Records every callback and answers `enter` with a per-opening choice
+#[derive(Default)]
+struct Trace {
+    events: Vec<String>,
+    skip: Option<usize>,
+    stop: Option<usize>
+}
+
+impl BlockVisitor<usize> for Trace {
+    fn enter(&mut self, block: &Block<Balanced, usize>, depth: usize) -> Visit {
+        self.events.push(format!("+{}@{depth}", block.opening()));
+        
+        match Some(block.opening()) {
+            opening if opening == self.skip => Visit::SkipChildren,
+            opening if opening == self.stop => Visit::Stop,
+            _ => Visit::Continue
+        }
+    }
+    
+    fn leave(&mut self, block: &Block<Balanced, usize>, depth: usize) -> Visit {
+        self.events.push(format!("-{}@{depth}", block.opening()));
+        Visit::Continue
+    }
+}
+
+fn blocks(code: &str) -> Vec<Block<Balanced, usize>> {
+    Blocks::scan(code, &Delimiters::brackets()).unwrap()
+}
+
+#[test]
+fn enter_and_leave_in_order() {
+    //                  0123456789
+    let blocks = blocks("(()[{}])()");
+    let mut trace = Trace::default();
+    
+    assert!(trace.walk(&blocks));
+    assert_eq!(trace.events.join(" "), "+0@0 +1@1 -1@1 +3@1 +4@2 -4@2 -3@1 -0@0 +8@0 -8@0");
+}
+
+#[test]
+fn skip_and_stop() {
+    let blocks = blocks("(()[{}])()");
+    
+    let mut trace = Trace {skip: Some(0), ..Trace::default()};
+    assert!(trace.walk(&blocks));
+    assert_eq!(trace.events.join(" "), "+0@0 -0@0 +8@0 -8@0");
+    
+    let mut trace = Trace {skip: Some(3), stop: Some(8), ..Trace::default()};
+    assert!(!trace.walk(&blocks));
+    assert_eq!(trace.events.join(" "), "+0@0 +1@1 -1@1 +3@1 -3@1 -0@0 +8@0");
+}
+
+#[test]
+fn deep_nesting() {
+    struct Depth(usize);
+    
+    impl BlockVisitor<usize> for Depth {
+        fn enter(&mut self, _: &Block<Balanced, usize>, depth: usize) -> Visit {
+            self.0 = self.0.max(depth);
+            Visit::Continue
+        }
+    }
+    
+    let code = format!("{}{}", "[".repeat(1_000_000), "]".repeat(1_000_000));
+    let nested = Blocks::scan(&code, &Delimiters::brackets()).unwrap();
+    let mut depth = Depth(0);
+    
+    assert!(depth.walk(&nested));
+    assert_eq!(depth.0, 999_999);
+    
+    let mut trace = Trace::default();
+    assert!(trace.walk_tree(&BlockTree::new(blocks("[]"))));
+    assert_eq!(trace.events, ["+0@0", "-0@0"]);
+}